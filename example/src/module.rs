@@ -39,7 +39,7 @@ fn main(args: &[&str]) -> Result<(), ()> {
 
 	let mut p = debug_value::advertise();
 
-	assert_eq!(p.is_advertised(), false);
+	assert!(!p.is_advertised());
 
 	p.publish(&debug_value {
 		timestamp: 123,
@@ -47,10 +47,10 @@ fn main(args: &[&str]) -> Result<(), ()> {
 		ind: 13,
 	}).unwrap();
 
-	assert_eq!(p.is_advertised(), true);
+	assert!(p.is_advertised());
 
-	p.publish(&debug_value {
-		timestamp: 456,
+	p.publish_now(&mut debug_value {
+		timestamp: 0,
 		value: 2.0f32,
 		ind: 37,
 	}).unwrap();
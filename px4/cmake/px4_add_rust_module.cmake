@@ -0,0 +1,128 @@
+# Link a Rust crate into the PX4 firmware as a builtin command.
+#
+# The crate must have `crate-type = ["staticlib"]`, and its entry point must
+# be marked with `#[px4_module_main(builtin = "<main>")]`.
+#
+# Usage, in the CMakeLists.txt of a module directory in the PX4 tree:
+#
+#   include(path/to/px4/cmake/px4_add_rust_module.cmake)
+#
+#   px4_add_rust_module(
+#       MODULE modules__blink
+#       MAIN blink
+#       CRATE_DIR ${CMAKE_CURRENT_SOURCE_DIR}/blink
+#       [CRATE_NAME blink]                 # Default: the name of CRATE_DIR.
+#       [TARGET thumbv7em-nuttx-eabihf]    # Default: the host.
+#       [FEATURES feature ...]             # Extra features of the crate.
+#       [MODULE_CONFIG module.yaml]        # Parameter definitions, see below.
+#       [STACK_MAIN 2048]
+#       [PRIORITY SCHED_PRIORITY_DEFAULT]
+#   )
+#
+# The `px4` crate needs the standard library, so for NuttX boards, TARGET must
+# be one of the `*-nuttx-*` targets rather than a `*-none-*` target. Those are
+# built with `-Z build-std`, using the nightly toolchain given by the
+# PX4_RUST_TOOLCHAIN cache variable (default: `nightly`), which must be
+# installed through rustup.
+#
+# Parameters declared with `#[px4_parameters]` only exist in the firmware if
+# they are in the MODULE_CONFIG file. Write the `MODULE_YAML` constant of the
+# struct to that file, e.g. from a test of the crate, and commit it: it is
+# not generated during the PX4 build.
+#
+# All Rust code in a firmware image shares one copy of the standard library,
+# so when using multiple Rust modules, put them in a single crate, each with
+# its own `builtin` name, and call px4_add_rust_module once for every MAIN.
+
+set(PX4_RUST_TOOLCHAIN "nightly" CACHE STRING
+	"The nightly Rust toolchain used for NuttX targets, which need -Z build-std")
+
+function(px4_add_rust_module)
+	cmake_parse_arguments(RUST
+		""
+		"MODULE;MAIN;CRATE_DIR;CRATE_NAME;TARGET;STACK_MAIN;PRIORITY;MODULE_CONFIG"
+		"FEATURES"
+		${ARGN}
+	)
+	foreach(arg MODULE MAIN CRATE_DIR)
+		if(NOT RUST_${arg})
+			message(FATAL_ERROR "px4_add_rust_module: ${arg} is required")
+		endif()
+	endforeach()
+
+	if(NOT RUST_CRATE_NAME)
+		get_filename_component(RUST_CRATE_NAME ${RUST_CRATE_DIR} NAME)
+	endif()
+	string(REPLACE "-" "_" lib_name ${RUST_CRATE_NAME})
+
+	set(cargo_target_dir ${CMAKE_BINARY_DIR}/cargo)
+	set(cargo cargo)
+	if(RUST_TARGET)
+		set(target_args --target ${RUST_TARGET})
+		if(RUST_TARGET MATCHES "nuttx")
+			# There is no prebuilt standard library for NuttX.
+			list(APPEND target_args -Z build-std=std,panic_abort)
+			execute_process(
+				COMMAND rustup run ${PX4_RUST_TOOLCHAIN} rustc --version
+				OUTPUT_VARIABLE rustc_version
+				RESULT_VARIABLE rustc_result
+				ERROR_QUIET
+			)
+			if(NOT rustc_result EQUAL 0 OR NOT rustc_version MATCHES "nightly")
+				message(FATAL_ERROR
+					"px4_add_rust_module: ${RUST_TARGET} needs a nightly Rust toolchain, "
+					"but PX4_RUST_TOOLCHAIN (${PX4_RUST_TOOLCHAIN}) is not an installed "
+					"nightly toolchain. Install it with `rustup toolchain install "
+					"${PX4_RUST_TOOLCHAIN} --component rust-src`, or set PX4_RUST_TOOLCHAIN."
+				)
+			endif()
+			set(cargo cargo +${PX4_RUST_TOOLCHAIN})
+		endif()
+		set(lib ${cargo_target_dir}/${RUST_TARGET}/release/lib${lib_name}.a)
+	else()
+		set(target_args)
+		set(lib ${cargo_target_dir}/release/lib${lib_name}.a)
+	endif()
+	if(RUST_FEATURES)
+		string(REPLACE ";" "," features "${RUST_FEATURES}")
+		set(feature_args --features ${features})
+	else()
+		set(feature_args)
+	endif()
+
+	# Always run cargo, which knows by itself whether anything changed.
+	add_custom_target(${RUST_MODULE}_cargo
+		COMMAND ${CMAKE_COMMAND} -E env CARGO_TARGET_DIR=${cargo_target_dir}
+			${cargo} build --release
+			--manifest-path ${RUST_CRATE_DIR}/Cargo.toml
+			${target_args}
+			${feature_args}
+		BYPRODUCTS ${lib}
+		COMMENT "Building Rust crate ${RUST_CRATE_NAME}"
+		USES_TERMINAL
+	)
+
+	# px4_add_module needs at least one source file.
+	set(stub ${CMAKE_CURRENT_BINARY_DIR}/${RUST_MAIN}_rust.c)
+	file(WRITE ${stub} "/* ${RUST_MAIN}_main is defined in lib${lib_name}.a */\n")
+
+	set(module_args)
+	if(RUST_STACK_MAIN)
+		list(APPEND module_args STACK_MAIN ${RUST_STACK_MAIN})
+	endif()
+	if(RUST_PRIORITY)
+		list(APPEND module_args PRIORITY ${RUST_PRIORITY})
+	endif()
+	if(RUST_MODULE_CONFIG)
+		list(APPEND module_args MODULE_CONFIG ${RUST_MODULE_CONFIG})
+	endif()
+
+	px4_add_module(
+		MODULE ${RUST_MODULE}
+		MAIN ${RUST_MAIN}
+		${module_args}
+		SRCS ${stub}
+		DEPENDS ${RUST_MODULE}_cargo
+	)
+	target_link_libraries(${RUST_MODULE} PRIVATE ${lib})
+endfunction()
@@ -0,0 +1,171 @@
+//! Arguments of the `#[px4_module_main]` function.
+//!
+//! PX4 passes arguments as C strings, which are not guaranteed to be valid
+//! UTF-8. The main function can take its arguments in one of these forms:
+//!
+//!  - `&[&str]`: All arguments must be valid UTF-8. If one isn't, an error is
+//!    logged and the module returns 1, without calling the main function.
+//!  - `&[&OsStr]`: The arguments as given, without any conversion.
+//!  - Any type that implements [`FromArgs`](trait.FromArgs.html), such as
+//!    `Vec<String>` or `Vec<OsString>`, or your own type to parse the
+//!    arguments into.
+//!
+//! In all cases, the first argument is the name of the module.
+//!
+//! If the module has [usage information](../usage/index.html), it is printed
+//! after the error message when the arguments can't be converted.
+//!
+//! ## Example
+//!
+//! ```
+//! use px4::args::{ArgsError, FromArgs};
+//! use px4::px4_module_main;
+//! use std::ffi::OsStr;
+//!
+//! struct Options {
+//!   rate: u32,
+//! }
+//!
+//! impl FromArgs for Options {
+//!   fn from_args(args: &[&OsStr]) -> Result<Self, ArgsError> {
+//!     let args = Vec::<String>::from_args(args)?;
+//!     match &args[1..] {
+//!       [rate] => Ok(Options {
+//!         rate: rate.parse().map_err(|e| ArgsError::new(format!("invalid rate: {}", e)))?,
+//!       }),
+//!       _ => Err(ArgsError::new("usage: my_module <rate>")),
+//!     }
+//!   }
+//! }
+//!
+//! #[px4_module_main]
+//! fn my_module(options: Options) {
+//!   // ...
+//! }
+//! ```
+
+use crate::usage::Usage;
+use crate::MainStatusCode;
+use log::error;
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+
+/// A type that can be created from the arguments of the module.
+///
+/// Implement this to use your own type as the argument of the
+/// `#[px4_module_main]` function.
+pub trait FromArgs: Sized {
+	/// Create `Self` from the arguments, including the name of the module.
+	///
+	/// On error, the error is logged and its status code is returned by the
+	/// module, without calling the main function.
+	fn from_args(args: &[&OsStr]) -> Result<Self, ArgsError>;
+}
+
+/// Fails if any of the arguments is not valid UTF-8.
+impl FromArgs for Vec<String> {
+	fn from_args(args: &[&OsStr]) -> Result<Self, ArgsError> {
+		Ok(to_str(args)?.into_iter().map(String::from).collect())
+	}
+}
+
+impl FromArgs for Vec<OsString> {
+	fn from_args(args: &[&OsStr]) -> Result<Self, ArgsError> {
+		Ok(args.iter().map(|&a| a.to_owned()).collect())
+	}
+}
+
+/// Convert all arguments to `&str`, or fail if one is not valid UTF-8.
+pub fn to_str<'a>(args: &[&'a OsStr]) -> Result<Vec<&'a str>, ArgsError> {
+	args.iter()
+		.enumerate()
+		.map(|(i, a)| {
+			a.to_str().ok_or_else(|| {
+				ArgsError::new(format!(
+					"invalid UTF-8 in argument {}: {:?}",
+					i,
+					a.to_string_lossy()
+				))
+			})
+		})
+		.collect()
+}
+
+/// Invalid arguments.
+///
+/// Contains the message to log, and the status code to return, which is 1
+/// unless set otherwise using [`with_status`](#method.with_status).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArgsError {
+	message: String,
+	status: i32,
+}
+
+impl ArgsError {
+	/// An error with the given message, and status code 1.
+	pub fn new(message: impl Into<String>) -> Self {
+		ArgsError {
+			message: message.into(),
+			status: 1,
+		}
+	}
+
+	/// Use a different status code.
+	pub fn with_status(mut self, status: i32) -> Self {
+		self.status = status;
+		self
+	}
+
+	/// The message.
+	pub fn message(&self) -> &str {
+		&self.message
+	}
+
+	/// The status code.
+	pub fn status(&self) -> i32 {
+		self.status
+	}
+}
+
+impl fmt::Display for ArgsError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(&self.message)
+	}
+}
+
+impl Error for ArgsError {}
+
+impl crate::ErrorStatusCode for ArgsError {
+	fn status_code(&self) -> i32 {
+		self.status
+	}
+}
+
+#[doc(hidden)]
+pub enum Status<R> {
+	Done(R),
+	Exit(i32),
+}
+
+#[doc(hidden)]
+pub fn _invalid<R>(target: &str, e: ArgsError, usage: Option<&Usage>) -> Status<R> {
+	error!(target: target, "{}", e);
+	if let Some(usage) = usage {
+		usage.print();
+	}
+	Status::Exit(e.status)
+}
+
+impl<R: MainStatusCode> MainStatusCode for Status<R> {
+	fn to_status_code(self) -> i32 {
+		match self {
+			Status::Done(r) => r.to_status_code(),
+			Status::Exit(status) => status,
+		}
+	}
+
+	fn panic_status_code() -> i32 {
+		R::panic_status_code()
+	}
+}
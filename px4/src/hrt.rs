@@ -0,0 +1,174 @@
+//! Bindings to the high-resolution timer of PX4.
+//!
+//! PX4 keeps time as the number of microseconds since the system was started.
+//! This is the clock used for the `timestamp` field of all uORB messages, and
+//! the clock returned by [`Subscription::stat`](../uorb/struct.Subscription.html#method.stat).
+//!
+//! When PX4 runs in lockstep with a simulator, this clock follows the
+//! simulated time, not the wall clock. Always use this module instead of
+//! `std::time` for anything that is compared to message timestamps.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! use px4::hrt::HrtInstant;
+//! use std::time::Duration;
+//!
+//! let sub = foo::subscribe().unwrap();
+//! let last_update = HrtInstant::from(sub.stat().unwrap());
+//! if last_update.elapsed() > Duration::from_millis(500) {
+//!   warn!("foo is stale");
+//! }
+//! ```
+
+use std::convert::TryFrom;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::time::Duration;
+
+#[cfg(feature = "mock")]
+use crate::mock::hrt::{hrt_absolute_time, px4_usleep};
+
+#[cfg(not(feature = "mock"))]
+extern "C" {
+	fn hrt_absolute_time() -> u64;
+	#[cfg(not(target_os = "nuttx"))]
+	fn px4_usleep(usec: u32) -> i32;
+	#[cfg(target_os = "nuttx")]
+	#[link_name = "usleep"]
+	fn px4_usleep(usec: u32) -> i32;
+}
+
+/// Get the current time, in microseconds since system start.
+///
+/// The equivalent of `hrt_absolute_time()` in C and C++.
+pub fn absolute_time() -> u64 {
+	unsafe { hrt_absolute_time() }
+}
+
+/// Get the time elapsed since the given timestamp, in microseconds.
+///
+/// The equivalent of `hrt_elapsed_time()` in C and C++.
+/// Returns 0 if `then` lies in the future.
+pub fn elapsed_time(then: u64) -> u64 {
+	absolute_time().saturating_sub(then)
+}
+
+/// Sleep for (at least) the given duration.
+///
+/// Uses `px4_usleep`, which, unlike `std::thread::sleep`, follows the
+/// simulated time when running in lockstep.
+pub fn sleep(duration: Duration) {
+	let mut micros = duration_to_micros(duration);
+	while micros > 0 {
+		let n = micros.min(u64::from(u32::MAX));
+		unsafe { px4_usleep(n as u32) };
+		micros -= n;
+	}
+}
+
+/// A point in time on the PX4 high-resolution clock.
+///
+/// This is a thin wrapper around the microsecond timestamps used throughout
+/// PX4, which can be converted to and from `u64` using `From`/`Into`.
+/// It behaves like `std::time::Instant`, but follows the PX4 clock instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HrtInstant(u64);
+
+impl HrtInstant {
+	/// The current time.
+	pub fn now() -> HrtInstant {
+		HrtInstant(absolute_time())
+	}
+
+	/// Create an instant from a timestamp in microseconds.
+	pub const fn from_micros(micros: u64) -> HrtInstant {
+		HrtInstant(micros)
+	}
+
+	/// The timestamp in microseconds.
+	pub const fn as_micros(self) -> u64 {
+		self.0
+	}
+
+	/// The time elapsed since this instant.
+	///
+	/// Returns zero if this instant lies in the future.
+	pub fn elapsed(self) -> Duration {
+		Duration::from_micros(elapsed_time(self.0))
+	}
+
+	/// The time elapsed from `earlier` to this instant.
+	///
+	/// Returns zero if `earlier` is later than this instant.
+	pub fn duration_since(self, earlier: HrtInstant) -> Duration {
+		self.checked_duration_since(earlier).unwrap_or_default()
+	}
+
+	/// The time elapsed from `earlier` to this instant, or `None` if `earlier`
+	/// is later than this instant.
+	pub fn checked_duration_since(self, earlier: HrtInstant) -> Option<Duration> {
+		self.0.checked_sub(earlier.0).map(Duration::from_micros)
+	}
+
+	/// `self + duration`, or `None` on overflow.
+	pub fn checked_add(self, duration: Duration) -> Option<HrtInstant> {
+		self.0.checked_add(duration_to_micros(duration)).map(HrtInstant)
+	}
+
+	/// `self - duration`, or `None` if that would be before system start.
+	pub fn checked_sub(self, duration: Duration) -> Option<HrtInstant> {
+		self.0.checked_sub(duration_to_micros(duration)).map(HrtInstant)
+	}
+}
+
+impl From<u64> for HrtInstant {
+	fn from(micros: u64) -> HrtInstant {
+		HrtInstant(micros)
+	}
+}
+
+impl From<HrtInstant> for u64 {
+	fn from(instant: HrtInstant) -> u64 {
+		instant.0
+	}
+}
+
+impl Add<Duration> for HrtInstant {
+	type Output = HrtInstant;
+	fn add(self, duration: Duration) -> HrtInstant {
+		self.checked_add(duration)
+			.expect("overflow when adding duration to instant")
+	}
+}
+
+impl AddAssign<Duration> for HrtInstant {
+	fn add_assign(&mut self, duration: Duration) {
+		*self = *self + duration;
+	}
+}
+
+impl Sub<Duration> for HrtInstant {
+	type Output = HrtInstant;
+	fn sub(self, duration: Duration) -> HrtInstant {
+		self.checked_sub(duration)
+			.expect("overflow when subtracting duration from instant")
+	}
+}
+
+impl SubAssign<Duration> for HrtInstant {
+	fn sub_assign(&mut self, duration: Duration) {
+		*self = *self - duration;
+	}
+}
+
+/// Returns the time between two instants, or zero if `earlier` is later.
+impl Sub<HrtInstant> for HrtInstant {
+	type Output = Duration;
+	fn sub(self, earlier: HrtInstant) -> Duration {
+		self.duration_since(earlier)
+	}
+}
+
+fn duration_to_micros(duration: Duration) -> u64 {
+	u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
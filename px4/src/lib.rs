@@ -1,8 +1,9 @@
 //! # PX4 bindings for Rust
 //!
 //! This crate provides the framework to make dynamically loadable PX4 modules
-//! in Rust. Right now, it provides bindings for the two most important APIs:
-//! Logging and uORB. It also provides the entry point for your module, and
+//! in Rust. Right now, it provides bindings for the most important APIs:
+//! Logging, uORB and parameters, as well as for the high-resolution timer used to
+//! timestamp messages. It also provides the entry point for your module, and
 //! handles panics on the main thread of the module.
 //!
 //! See the
@@ -31,16 +32,53 @@
 //! If you want to run a changed version of your module, you'll either need to
 //! restart PX4, or move/rename the file.
 //!
+//! ### Linking into the firmware
+//!
+//! To include a module in the firmware image as a builtin command, instead of
+//! loading it with `dyn`, build it as a `staticlib`, and give the name of the
+//! command to the entry point: `#[px4_module_main(builtin = "blink")]`. This
+//! exports a `blink_main` function, like the C++ modules have, instead of the
+//! `px4_module_main` function used by `dyn`.
+//!
+//! The
+//! [`px4_add_rust_module`](https://github.com/dronesforwork/px4-rust/tree/master/px4/cmake/px4_add_rust_module.cmake)
+//! CMake function in this crate's `cmake` directory does all of this, and
+//! registers the module with `px4_add_module`.
+//!
+//! ## Testing without PX4
+//!
+//! With the `mock` feature enabled, the uORB, timer, task, parameter and
+//! logging bindings use an in-process implementation instead of PX4's, such
+//! that code using them can be tested with `cargo test` on a development
+//! machine. A whole module can be run with
+//! [`mock::run_module`](mock/fn.run_module.html), to check its status code
+//! and everything it logged.
+//! See the [`mock` module](mock/index.html), which only exists with this
+//! feature enabled.
+//!
 //! ## Entry point
 //!
 //! Mark your entry function with `#[px4_module_main]`. The boilerplate code
 //! needed to set up the environment and export the function under the right
 //! name is then inserted automatically.
 //!
-//! Your main function should take a `&[&str]` as argument. It *may* return a
+//! Your main function should take a `&[&str]` as argument, or one of the
+//! other argument types described in the [`args` module](args/index.html).
+//! Arguments that are not valid UTF-8 are reported as an error with status
+//! code 1, rather than passed as `&str`. It *may* return a
 //! `i32` status code, either directly, or as the error type of a `Result`.  A
 //! panic from your main thread is caught and results in a status code of −1.
 //!
+//! It may also return a `Result` with any other error type that implements
+//! `Display` or `Debug`, such as `Box<dyn Error>`. An error is then logged at
+//! error level, and results in a status code of 1. To use a different status
+//! code for your own error type, implement
+//! [`ErrorStatusCode`](trait.ErrorStatusCode.html) for it.
+//!
+//! To print PX4-style usage information on `help`, give a description of the
+//! module's commands as `#[px4_module_main(usage = "USAGE")]`. See the
+//! [`usage` module](usage/index.html).
+//!
 //! ### Example
 //!
 //! ```
@@ -52,6 +90,73 @@
 //! }
 //! ```
 //!
+//! ```
+//! use px4::px4_module_main;
+//! use std::error::Error;
+//!
+//! #[px4_module_main]
+//! fn my_module(args: &[&str]) -> Result<(), Box<dyn Error>> {
+//!   let n: u32 = args.get(1).ok_or("missing argument")?.parse()?;
+//!   Ok(())
+//! }
+//! ```
+//!
+//! ### Multiple commands
+//!
+//! To bundle several commands in one module, put `#[px4_module_main]` on a
+//! `mod` instead, and mark each command function in it with
+//! `#[px4_command]`. The first argument then selects the function to call,
+//! which gets the remaining arguments, starting with the command name. Each
+//! command function can take and return any of the types described above.
+//!
+//! The attribute of each command can be written as `#[px4_command]` without
+//! importing it (and the same goes for `#[px4_setup]` below), and can set a
+//! `name` and `description`. Otherwise the function name and the first line
+//! of its doc comment are used. A `USAGE` static with the
+//! [usage information](usage/index.html) is added to the `mod`, and printed
+//! on `help`, or on a missing or unknown command.
+//!
+//! Setup shared by all commands goes in a function marked with
+//! `#[px4_setup]`, which takes no arguments. It is called before the selected
+//! command, which then takes its result as an extra first argument. If it
+//! returns a `Result`, an error is reported like an error returned from a
+//! command, and the command gets the `Ok` value.
+//!
+//! ```
+//! use px4::px4_module_main;
+//!
+//! /// Tools for the foo sensor.
+//! #[px4_module_main]
+//! mod footool {
+//!   pub struct Sensor;
+//!
+//!   #[px4_setup]
+//!   pub fn open() -> Result<Sensor, String> {
+//!     Ok(Sensor)
+//!   }
+//!
+//!   /// Calibrate the sensor.
+//!   #[px4_command]
+//!   pub fn calibrate(sensor: Sensor, args: &[&str]) -> i32 {
+//!     0
+//!   }
+//!
+//!   #[px4_command(name = "mon", description = "Monitor the sensor")]
+//!   pub fn monitor(sensor: Sensor, args: &[&str]) {
+//!   }
+//! }
+//! ```
+//!
+//! ## Background tasks
+//!
+//! Modules that keep running in the background, with `start`, `stop` and
+//! `status` commands like PX4's C++ modules, can be made by implementing the
+//! [`Module`](module/trait.Module.html) trait. See the
+//! [`module` module](module/index.html).
+//!
+//! To start other tasks with a PX4 name and scheduling priority, use
+//! [`task::spawn`](task/fn.spawn.html) instead of `std::thread::spawn`.
+//!
 //! ## Logging
 //!
 //! As soon as your main function is entered, logging is already set up using
@@ -59,12 +164,54 @@
 //! logging macros such as `info!`, `warn!`, and `error!` to log messages,
 //! equivalent to `PX4_INFO` (etc.) in C and C++.
 //!
+//! By default, messages up to `info` are logged, and `debug!` and `trace!`
+//! are discarded. Set a different [`LogFilter`](struct.LogFilter.html),
+//! such as `warn,my_module::control=debug`, through the `PX4_RUST_LOG`
+//! environment variable, with [`set_log_filter`](fn.set_log_filter.html)
+//! (e.g. from an argument), from a parameter with
+//! [`set_log_level_from_param`](fn.set_log_level_from_param.html), or at
+//! runtime with the `log` command of a [`Module`](module/trait.Module.html)
+//! (see [`log_command`](fn.log_command.html)).
+//!
+//! Messages are shown with the name of the command in front, such as
+//! `[my_module]`: the name of the crate, or the name given with
+//! `#[px4_module_main(builtin = "...")]`. Tasks started with
+//! [`task::spawn`](task/fn.spawn.html) use the name of the command that
+//! started them. Use [`set_log_tag`](fn.set_log_tag.html) to choose another
+//! name, or to also show the target (the Rust module path) of each message.
+//!
+//! To keep messages in fast loops from flooding the console, use the
+//! rate-limited variants of the logging macros:
+//! [`warn_throttled!`](macro.warn_throttled.html) logs at most once per given
+//! period, [`info_once!`](macro.info_once.html) only once, and
+//! [`error_every_n!`](macro.error_every_n.html) every `n`th time, reporting
+//! how many messages were suppressed in between. They exist for the `error`,
+//! `warn`, `info` and `debug` levels.
+//!
+//! Like in C and C++, warnings and errors are also stored in the flight log.
+//! To store info and debug messages as well, enable publishing them as
+//! `log_message` with [`set_log_forwarding`](fn.set_log_forwarding.html).
+//!
 //! Use the `info_raw!` macro to send raw output, equivalent to the
 //! `PX4_INFO_RAW` macro in C and C++.
 //! Do not use standard output or standard error for this, as the standard
 //! streams of the PX4 process are often not the ones connected to the terminal
 //! the user is looking at.
 //!
+//! Logging does not allocate, such that it can be used in real-time loops.
+//! Messages are formatted into a buffer of
+//! [`LOG_BUFFER_SIZE`](constant.LOG_BUFFER_SIZE.html) bytes on the stack,
+//! and longer messages are cut off and end in
+//! [`TRUNCATION_MARKER`](constant.TRUNCATION_MARKER.html). Raw output is not
+//! truncated, but passed on in parts of at most that size.
+//!
+//! Panics are logged as well, in any thread, with a backtrace in debug
+//! builds. A crash record is written to `rust_panic_<module>.txt` in PX4's
+//! storage directory, and reported the next time the module is started. The
+//! number of panics is shown by the `status` command of a
+//! [`Module`](module/trait.Module.html). See
+//! [`PanicReport`](struct.PanicReport.html).
+//!
 //! ### Example
 //!
 //! ```
@@ -79,6 +226,11 @@
 //! }
 //! ```
 //!
+//! ## Parameters
+//!
+//! PX4 parameters can be read and changed through typed handles. See the
+//! [`param` module](param/index.html).
+//!
 //! ## uORB
 //!
 //! Message definitions can be imported from `.msg` files, and then subscribed
@@ -106,31 +258,53 @@
 //! }
 //! ```
 
-use std::ffi::CStr;
+// Allows the generated code of `#[px4_message]` to be used inside this crate.
+extern crate self as px4;
+
+use std::ffi::{CStr, OsStr};
 use std::os::raw::c_char;
+use std::os::unix::ffi::OsStrExt;
 
+pub mod args;
+pub mod hrt;
 mod logging;
+#[doc(hidden)]
+pub mod main_status;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod module;
+pub mod param;
+mod should_exit;
+pub mod task;
 pub mod uorb;
+pub mod usage;
 
-pub use crate::logging::{log_raw, LogLevel};
-pub use px4_macros::{px4_message, px4_module_main};
+#[doc(hidden)]
+pub use crate::logging::limit as _log_limit;
+#[doc(hidden)]
+pub use crate::logging::log_raw_fmt as _log_raw_fmt;
+#[doc(hidden)]
+pub use log as _log;
+pub use crate::logging::{
+	log_command, log_filter, log_message, log_raw, log_tag, panic_count, panic_report,
+	set_log_filter, set_log_forwarding, set_log_level_from_param, set_log_tag, set_panic_report,
+	LogFilter, LogFilterError, LogForwarding, LogLevel, LogTag, PanicReport, LOG_BUFFER_SIZE,
+	LOG_FILTER_ENV, STORAGE_DIR, TRUNCATION_MARKER,
+};
+pub use crate::should_exit::ShouldExit;
+pub use px4_macros::{px4_command, px4_message, px4_module_main, px4_parameters, px4_setup};
 
 #[doc(hidden)]
 pub unsafe fn _run<F, R>(modulename: &'static [u8], argc: u32, argv: *mut *mut u8, f: F) -> i32
 where
-	F: Fn(&[&str]) -> R + std::panic::UnwindSafe,
+	F: Fn(&[&OsStr]) -> R + std::panic::UnwindSafe,
 	R: MainStatusCode,
 {
-	logging::init(modulename);
+	let _tag = logging::init(modulename);
 	std::panic::catch_unwind(move || {
-		let mut args = Vec::with_capacity(argc as usize);
-		for i in 0..argc {
-			args.push(
-				CStr::from_ptr(*argv.offset(i as isize) as *const c_char)
-					.to_str()
-					.unwrap_or_else(|_| panic!("Invalid UTF-8 in arguments.")),
-			);
-		}
+		let args: Vec<&OsStr> = (0..argc as usize)
+			.map(|i| OsStr::from_bytes(CStr::from_ptr(*argv.add(i) as *const c_char).to_bytes()))
+			.collect();
 		f(&args).to_status_code()
 	}).unwrap_or(R::panic_status_code())
 }
@@ -148,6 +322,45 @@ pub trait MainStatusCode {
 	}
 }
 
+/// An error type with its own status code, for `#[px4_module_main]` functions
+/// returning `Result<T, E>`.
+///
+/// Without this, errors that implement `Display` or `Debug` are logged and
+/// result in a status code of 1. Implement this trait to choose a different
+/// status code. The error is still logged using `Display`.
+///
+/// ```
+/// use px4::{px4_module_main, ErrorStatusCode};
+/// use std::fmt;
+///
+/// struct BusyError;
+///
+/// impl fmt::Display for BusyError {
+///   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///     write!(f, "device busy")
+///   }
+/// }
+///
+/// impl ErrorStatusCode for BusyError {
+///   fn status_code(&self) -> i32 {
+///     16
+///   }
+/// }
+///
+/// #[px4_module_main]
+/// fn my_module(args: &[&str]) -> Result<(), BusyError> {
+///   Err(BusyError)
+/// }
+/// ```
+pub trait ErrorStatusCode: std::fmt::Display {
+	/// The status code to return for this error.
+	///
+	/// 1 by default.
+	fn status_code(&self) -> i32 {
+		1
+	}
+}
+
 /// Returns 0.
 impl MainStatusCode for () {
 	fn to_status_code(self) -> i32 {
@@ -0,0 +1,108 @@
+//! Formatting log messages into buffers on the stack, such that logging
+//! doesn't allocate.
+
+use std::fmt::{self, Write};
+
+/// The size of the buffer for a log message, including the terminating nul.
+///
+/// Longer messages are truncated, and end in [`TRUNCATION_MARKER`].
+pub const LOG_BUFFER_SIZE: usize = 256;
+
+/// The size of the buffer for the module name, including the terminating nul.
+pub const MODULE_BUFFER_SIZE: usize = 64;
+
+/// Appended to messages that didn't fit in the buffer.
+pub const TRUNCATION_MARKER: &str = "[...]";
+
+/// Writes into a fixed buffer, stopping at the last character that fits.
+struct Truncating<'a> {
+	buf: &'a mut [u8],
+	len: usize,
+	truncated: bool,
+}
+
+impl Write for Truncating<'_> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		if self.truncated {
+			return Err(fmt::Error);
+		}
+		let mut n = s.len().min(self.buf.len() - self.len);
+		while !s.is_char_boundary(n) {
+			n -= 1;
+		}
+		self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+		self.len += n;
+		if n < s.len() {
+			self.truncated = true;
+			return Err(fmt::Error);
+		}
+		Ok(())
+	}
+}
+
+/// Format `args` into `buf`, followed by a nul byte.
+///
+/// If it doesn't fit, it is cut off at a character boundary, and `marker`
+/// is appended. Returns the formatted string, without the nul byte.
+pub fn format_nul<'a>(buf: &'a mut [u8], args: fmt::Arguments, marker: &str) -> &'a str {
+	let limit = buf.len() - marker.len() - 1;
+	let mut w = Truncating {
+		buf: &mut buf[..limit],
+		len: 0,
+		truncated: false,
+	};
+	let _ = w.write_fmt(args);
+	let (mut len, truncated) = (w.len, w.truncated);
+	if truncated {
+		buf[len..len + marker.len()].copy_from_slice(marker.as_bytes());
+		len += marker.len();
+	}
+	buf[len] = 0;
+	// Only whole characters were copied.
+	std::str::from_utf8(&buf[..len]).unwrap()
+}
+
+/// Writes raw output through a buffer, passing it on in chunks whenever the
+/// buffer is full.
+pub struct Chunked<F: FnMut(&[u8])> {
+	buf: [u8; LOG_BUFFER_SIZE],
+	len: usize,
+	flush: F,
+}
+
+impl<F: FnMut(&[u8])> Chunked<F> {
+	pub fn new(flush: F) -> Self {
+		Chunked {
+			buf: [0; LOG_BUFFER_SIZE],
+			len: 0,
+			flush,
+		}
+	}
+
+	pub fn finish(mut self) {
+		if self.len > 0 {
+			(self.flush)(&self.buf[..self.len]);
+		}
+	}
+}
+
+impl<F: FnMut(&[u8])> Write for Chunked<F> {
+	fn write_str(&mut self, mut s: &str) -> fmt::Result {
+		while !s.is_empty() {
+			let mut n = s.len().min(self.buf.len() - self.len);
+			while !s.is_char_boundary(n) {
+				n -= 1;
+			}
+			if n == 0 {
+				// Flush without splitting a character.
+				(self.flush)(&self.buf[..self.len]);
+				self.len = 0;
+				continue;
+			}
+			self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+			self.len += n;
+			s = &s[n..];
+		}
+		Ok(())
+	}
+}
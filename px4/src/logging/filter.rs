@@ -0,0 +1,200 @@
+use log::LevelFilter;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{RwLock, RwLockReadGuard};
+
+/// The environment variable read when logging is set up.
+pub const LOG_FILTER_ENV: &str = "PX4_RUST_LOG";
+
+/// Which messages to log, per target.
+///
+/// Written like `env_logger`'s filters: a comma separated list of
+/// `target=level` directives, and optionally a level without target, which
+/// applies to all other targets. For example, `warn,my_module::control=debug`.
+/// The target of a message is the module path it is logged from, unless
+/// given explicitly.
+///
+/// The levels are `off`, `error`, `warn`, `info`, `debug` and `trace`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogFilter {
+	default: LevelFilter,
+	directives: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+	/// A filter which logs everything up to the given level, for all targets.
+	pub const fn new(level: LevelFilter) -> Self {
+		LogFilter {
+			default: level,
+			directives: Vec::new(),
+		}
+	}
+
+	/// Also log everything up to `level` for `target` and its submodules.
+	pub fn with_target(mut self, target: &str, level: LevelFilter) -> Self {
+		self.directives.retain(|d| d.0 != target);
+		self.directives.push((target.to_string(), level));
+		self
+	}
+
+	/// The maximum level logged for the given target.
+	pub fn level_for(&self, target: &str) -> LevelFilter {
+		self.directives
+			.iter()
+			.filter(|(t, _)| {
+				target.strip_prefix(t.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+			})
+			.max_by_key(|(t, _)| t.len())
+			.map_or(self.default, |d| d.1)
+	}
+
+	/// The maximum level logged for any target.
+	pub fn max_level(&self) -> LevelFilter {
+		self.directives
+			.iter()
+			.map(|d| d.1)
+			.fold(self.default, Ord::max)
+	}
+}
+
+/// Logs `info` and more severe messages.
+impl Default for LogFilter {
+	fn default() -> Self {
+		LogFilter::new(LevelFilter::Info)
+	}
+}
+
+impl FromStr for LogFilter {
+	type Err = LogFilterError;
+
+	/// Parse a filter like `warn,my_module=debug`.
+	///
+	/// Without a level for all targets, `info` is used.
+	fn from_str(s: &str) -> Result<Self, LogFilterError> {
+		let mut filter = LogFilter::default();
+		for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+			let error = || LogFilterError(directive.to_string());
+			match directive.split_once('=') {
+				Some((target, level)) => {
+					let target = target.trim();
+					if target.is_empty() {
+						return Err(error());
+					}
+					filter = filter.with_target(target, level.trim().parse().map_err(|_| error())?);
+				}
+				None => filter.default = directive.parse().map_err(|_| error())?,
+			}
+		}
+		Ok(filter)
+	}
+}
+
+/// Formats the filter in the syntax accepted by `from_str`.
+impl fmt::Display for LogFilter {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.default.as_str().to_lowercase())?;
+		for (target, level) in &self.directives {
+			write!(f, ",{}={}", target, level.as_str().to_lowercase())?;
+		}
+		Ok(())
+	}
+}
+
+/// An invalid directive in a log filter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogFilterError(String);
+
+impl fmt::Display for LogFilterError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "invalid log filter directive `{}`", self.0)
+	}
+}
+
+impl Error for LogFilterError {}
+
+static FILTER: RwLock<LogFilter> = RwLock::new(LogFilter::new(LevelFilter::Info));
+
+pub(super) fn current() -> RwLockReadGuard<'static, LogFilter> {
+	FILTER.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Set the filter used for all messages logged through the `log` crate.
+///
+/// The filter is shared by everything in the same module library, also by
+/// tasks started earlier, and stays in effect until changed again.
+pub fn set_log_filter(filter: LogFilter) {
+	log::set_max_level(filter.max_level());
+	*FILTER.write().unwrap_or_else(|e| e.into_inner()) = filter;
+}
+
+/// The filter currently in use.
+pub fn log_filter() -> LogFilter {
+	current().clone()
+}
+
+/// Set the filter from a parameter with a log level: 0 for `off`, 1 for
+/// `error`, up to 5 for `trace`.
+///
+/// Only changes the level used for targets without their own level.
+pub fn set_log_level_from_param(name: &str) -> Result<(), crate::param::ParamError> {
+	let level = crate::param::Param::<i32>::find(name)?.get();
+	let mut filter = log_filter();
+	filter.default = match level {
+		i32::MIN..=0 => LevelFilter::Off,
+		1 => LevelFilter::Error,
+		2 => LevelFilter::Warn,
+		3 => LevelFilter::Info,
+		4 => LevelFilter::Debug,
+		_ => LevelFilter::Trace,
+	};
+	set_log_filter(filter);
+	Ok(())
+}
+
+/// Handle a `log` command: show the current filter, or set a new one.
+///
+/// `args` are the arguments of the command, starting with the command name
+/// itself, e.g. `["log", "debug,my_module::io=trace"]`. Returns the status
+/// code. [`module::main`](module/fn.main.html) uses this for the `log`
+/// command of every [`Module`](module/trait.Module.html).
+pub fn log_command(args: &[&str]) -> i32 {
+	match args {
+		[_] => {
+			crate::info_raw!("log filter: {}\n", log_filter());
+			0
+		}
+		[_, spec] => match spec.parse() {
+			Ok(filter) => {
+				set_log_filter(filter);
+				0
+			}
+			Err(e) => {
+				crate::info_raw!("{}\n", e);
+				1
+			}
+		},
+		_ => {
+			crate::info_raw!("usage: log [<filter>]\n");
+			1
+		}
+	}
+}
+
+/// Apply `PX4_RUST_LOG`, if set.
+pub(super) fn init() {
+	let spec = match std::env::var(LOG_FILTER_ENV) {
+		Ok(spec) => spec,
+		Err(_) => {
+			log::set_max_level(current().max_level());
+			return;
+		}
+	};
+	match spec.parse() {
+		Ok(filter) => set_log_filter(filter),
+		Err(e) => {
+			log::set_max_level(current().max_level());
+			log::warn!(target: "px4", "{}: {}", LOG_FILTER_ENV, e);
+		}
+	}
+}
@@ -0,0 +1,169 @@
+use super::buffer::format_nul;
+use super::LogLevel;
+use crate::hrt;
+use crate::px4_message;
+use crate::uorb::{Publish, Publisher};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The message PX4 uses to store log messages in the flight log.
+///
+/// See [`set_log_forwarding`](fn.set_log_forwarding.html).
+#[px4_message("msg/log_message.msg")]
+pub struct log_message;
+
+impl log_message {
+	fn new(severity: u8, message: &str) -> Self {
+		let mut text = [0u8; 127];
+		let mut len = message.len().min(text.len() - 1);
+		while !message.is_char_boundary(len) {
+			len -= 1;
+		}
+		text[..len].copy_from_slice(&message.as_bytes()[..len]);
+		log_message {
+			timestamp: 0,
+			severity,
+			text,
+		}
+	}
+
+	/// The severity, as used by syslog: 0 for emergencies, 3 for errors, 4
+	/// for warnings, 6 for info, and 7 for debug messages.
+	pub fn severity(&self) -> u8 {
+		self.severity
+	}
+
+	/// The message, truncated to 126 bytes.
+	pub fn text(&self) -> &str {
+		let len = self.text.iter().position(|&b| b == 0).unwrap_or(self.text.len());
+		std::str::from_utf8(&self.text[..len]).unwrap_or("")
+	}
+}
+
+/// Which log records to publish as [`log_message`](struct.log_message.html),
+/// and how often.
+///
+/// PX4 itself publishes warnings and more severe messages as `log_message`,
+/// so only info and debug messages are published here.
+///
+/// Up to `burst` messages are published at once. After that, one more
+/// message is allowed every `interval`. Messages over the limit are dropped,
+/// and the number of dropped messages is published before the next message
+/// that is allowed through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogForwarding {
+	/// The least severe level to publish. Has no effect if it is `Warn` or
+	/// above.
+	pub level: LogLevel,
+	pub burst: u32,
+	pub interval: Duration,
+}
+
+/// Publishes info messages, at most 5 at once and then one per second.
+impl Default for LogForwarding {
+	fn default() -> Self {
+		LogForwarding {
+			level: LogLevel::Info,
+			burst: 5,
+			interval: Duration::from_secs(1),
+		}
+	}
+}
+
+struct State {
+	config: LogForwarding,
+	publisher: Option<Publisher<log_message>>,
+	tokens: u32,
+	last_refill: u64,
+	dropped: u32,
+}
+
+/// The least severe level to forward, or `u8::MAX` if not forwarding.
+static MIN_LEVEL: AtomicU8 = AtomicU8::new(u8::MAX);
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+/// Also publish info and debug messages as `log_message`, such that they are
+/// stored in the flight log, like PX4 does for warnings and errors.
+///
+/// Messages are not sent to ground stations this way: those only get the
+/// messages published as `mavlink_log`, by `mavlink_log_*` in C and C++.
+///
+/// `None` disables forwarding, which is the default.
+pub fn set_log_forwarding(forwarding: Option<LogForwarding>) {
+	let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+	match forwarding {
+		Some(config) => {
+			let publisher = state.take().and_then(|s| s.publisher);
+			*state = Some(State {
+				config,
+				publisher,
+				tokens: config.burst,
+				last_refill: hrt::absolute_time(),
+				dropped: 0,
+			});
+			MIN_LEVEL.store(config.level as u8, Ordering::Relaxed);
+		}
+		None => {
+			MIN_LEVEL.store(u8::MAX, Ordering::Relaxed);
+			*state = None;
+		}
+	}
+}
+
+fn severity(level: LogLevel) -> u8 {
+	match level {
+		LogLevel::Debug => 7,
+		LogLevel::Info => 6,
+		LogLevel::Warn => 4,
+		LogLevel::Error => 3,
+		LogLevel::Panic => 0,
+	}
+}
+
+/// Publish the message, if forwarding is enabled for this level and the
+/// rate limit allows it. Warnings and errors are already published by
+/// `px4_log_modulename`.
+///
+/// This must not log anything itself.
+pub(super) fn forward(level: LogLevel, message: &str) {
+	if level >= LogLevel::Warn || (level as u8) < MIN_LEVEL.load(Ordering::Relaxed) {
+		return;
+	}
+	let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+	let state = match &mut *state {
+		Some(state) if level >= state.config.level => state,
+		_ => return,
+	};
+
+	// Refill the tokens for the time passed.
+	let now = hrt::absolute_time();
+	let interval = (state.config.interval.as_micros() as u64).max(1);
+	let new_tokens = (now.saturating_sub(state.last_refill) / interval).min(u64::from(u32::MAX)) as u32;
+	if new_tokens > 0 {
+		state.tokens = state.tokens.saturating_add(new_tokens).min(state.config.burst);
+		state.last_refill = if state.tokens == state.config.burst {
+			now
+		} else {
+			state.last_refill + u64::from(new_tokens) * interval
+		};
+	}
+
+	if state.tokens == 0 {
+		state.dropped = state.dropped.saturating_add(1);
+		return;
+	}
+	state.tokens -= 1;
+
+	let publisher = state
+		.publisher
+		.get_or_insert_with(|| log_message::advertise_queue(4));
+	if state.dropped > 0 {
+		let mut buf = [0u8; 64];
+		let notice = format_nul(&mut buf, format_args!("{} log messages dropped", state.dropped), "");
+		let _ = publisher.publish_now(&mut log_message::new(severity(LogLevel::Warn), notice));
+		state.dropped = 0;
+	}
+	let _ = publisher.publish_now(&mut log_message::new(severity(level), message));
+}
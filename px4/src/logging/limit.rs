@@ -0,0 +1,232 @@
+//! The per-callsite state of the rate-limited logging macros.
+
+use crate::hrt;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// State of `log_throttled!`.
+#[doc(hidden)]
+pub struct Throttle {
+	/// The time of the last message, plus one, or zero if there was none.
+	last: AtomicU64,
+	suppressed: AtomicU32,
+}
+
+impl Throttle {
+	#[allow(clippy::new_without_default)]
+	pub const fn new() -> Self {
+		Throttle {
+			last: AtomicU64::new(0),
+			suppressed: AtomicU32::new(0),
+		}
+	}
+
+	/// Returns the number of suppressed messages if a message should be
+	/// logged now, or `None` if it should be suppressed.
+	pub fn check(&self, period: Duration) -> Option<u32> {
+		let now = hrt::absolute_time() + 1;
+		let last = self.last.load(Ordering::Relaxed);
+		let period = period.as_micros().min(u64::MAX as u128) as u64;
+		if last != 0 && now.saturating_sub(last) < period {
+			self.suppressed.fetch_add(1, Ordering::Relaxed);
+			return None;
+		}
+		if self
+			.last
+			.compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+			.is_err()
+		{
+			// Another thread logged just now.
+			self.suppressed.fetch_add(1, Ordering::Relaxed);
+			return None;
+		}
+		Some(self.suppressed.swap(0, Ordering::Relaxed))
+	}
+}
+
+/// State of `log_once!`.
+#[doc(hidden)]
+pub struct Once(AtomicBool);
+
+impl Once {
+	#[allow(clippy::new_without_default)]
+	pub const fn new() -> Self {
+		Once(AtomicBool::new(false))
+	}
+
+	/// Returns true only the first time.
+	pub fn check(&self) -> bool {
+		!self.0.swap(true, Ordering::Relaxed)
+	}
+}
+
+/// State of `log_every_n!`.
+#[doc(hidden)]
+pub struct EveryN(AtomicU64);
+
+impl EveryN {
+	#[allow(clippy::new_without_default)]
+	pub const fn new() -> Self {
+		EveryN(AtomicU64::new(0))
+	}
+
+	/// Returns the number of suppressed messages for the first and every
+	/// `n`th call after that, or `None` for the others.
+	pub fn check(&self, n: u64) -> Option<u32> {
+		let i = self.0.fetch_add(1, Ordering::Relaxed);
+		let n = n.max(1);
+		if i % n == 0 {
+			Some(if i == 0 { 0 } else { (n - 1).min(u64::from(u32::MAX)) as u32 })
+		} else {
+			None
+		}
+	}
+}
+
+/// Log a message at most once per `period`, as measured by the PX4 clock.
+///
+/// Takes a `log::Level`, a `Duration`, and the same arguments as `log!`.
+/// The state is kept per call site. When a message is logged again after
+/// others were suppressed, their number is appended to the message.
+///
+/// Use the shorthands such as [`warn_throttled!`](macro.warn_throttled.html)
+/// for a fixed level.
+///
+/// ```
+/// # use px4::warn_throttled;
+/// # use std::time::Duration;
+/// # let altitude = 10.0;
+/// for _ in 0..1000 {
+///   warn_throttled!(Duration::from_secs(1), "altitude estimate invalid: {}", altitude);
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_throttled {
+	($level:expr, $period:expr, $($arg:tt)+) => {{
+		static STATE: $crate::_log_limit::Throttle = $crate::_log_limit::Throttle::new();
+		if let Some(suppressed) = STATE.check($period) {
+			$crate::_log_suppressed!($level, suppressed, $($arg)+);
+		}
+	}};
+}
+
+/// Log a message only the first time this line is reached.
+///
+/// Takes a `log::Level` and the same arguments as `log!`.
+/// Use the shorthands such as [`info_once!`](macro.info_once.html) for a
+/// fixed level.
+#[macro_export]
+macro_rules! log_once {
+	($level:expr, $($arg:tt)+) => {{
+		static STATE: $crate::_log_limit::Once = $crate::_log_limit::Once::new();
+		if STATE.check() {
+			$crate::_log::log!($level, $($arg)+);
+		}
+	}};
+}
+
+/// Log a message the first time and then every `n`th time this line is
+/// reached.
+///
+/// Takes a `log::Level`, a number, and the same arguments as `log!`.
+/// When a message is logged, the number of suppressed messages is appended.
+/// Use the shorthands such as [`error_every_n!`](macro.error_every_n.html)
+/// for a fixed level.
+#[macro_export]
+macro_rules! log_every_n {
+	($level:expr, $n:expr, $($arg:tt)+) => {{
+		static STATE: $crate::_log_limit::EveryN = $crate::_log_limit::EveryN::new();
+		if let Some(suppressed) = STATE.check($n) {
+			$crate::_log_suppressed!($level, suppressed, $($arg)+);
+		}
+	}};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _log_suppressed {
+	($level:expr, $suppressed:expr, $($arg:tt)+) => {
+		if $suppressed == 0 {
+			$crate::_log::log!($level, $($arg)+);
+		} else {
+			$crate::_log::log!(
+				$level,
+				"{} ({} similar messages suppressed)",
+				format_args!($($arg)+),
+				$suppressed
+			);
+		}
+	};
+}
+
+/// [`log_throttled!`](macro.log_throttled.html) at the `error` level.
+#[macro_export]
+macro_rules! error_throttled {
+	($period:expr, $($arg:tt)+) => ($crate::log_throttled!($crate::_log::Level::Error, $period, $($arg)+));
+}
+
+/// [`log_once!`](macro.log_once.html) at the `error` level.
+#[macro_export]
+macro_rules! error_once {
+	($($arg:tt)+) => ($crate::log_once!($crate::_log::Level::Error, $($arg)+));
+}
+
+/// [`log_every_n!`](macro.log_every_n.html) at the `error` level.
+#[macro_export]
+macro_rules! error_every_n {
+	($n:expr, $($arg:tt)+) => ($crate::log_every_n!($crate::_log::Level::Error, $n, $($arg)+));
+}
+
+/// [`log_throttled!`](macro.log_throttled.html) at the `warn` level.
+#[macro_export]
+macro_rules! warn_throttled {
+	($period:expr, $($arg:tt)+) => ($crate::log_throttled!($crate::_log::Level::Warn, $period, $($arg)+));
+}
+
+/// [`log_once!`](macro.log_once.html) at the `warn` level.
+#[macro_export]
+macro_rules! warn_once {
+	($($arg:tt)+) => ($crate::log_once!($crate::_log::Level::Warn, $($arg)+));
+}
+
+/// [`log_every_n!`](macro.log_every_n.html) at the `warn` level.
+#[macro_export]
+macro_rules! warn_every_n {
+	($n:expr, $($arg:tt)+) => ($crate::log_every_n!($crate::_log::Level::Warn, $n, $($arg)+));
+}
+
+/// [`log_throttled!`](macro.log_throttled.html) at the `info` level.
+#[macro_export]
+macro_rules! info_throttled {
+	($period:expr, $($arg:tt)+) => ($crate::log_throttled!($crate::_log::Level::Info, $period, $($arg)+));
+}
+
+/// [`log_once!`](macro.log_once.html) at the `info` level.
+#[macro_export]
+macro_rules! info_once {
+	($($arg:tt)+) => ($crate::log_once!($crate::_log::Level::Info, $($arg)+));
+}
+
+/// [`log_every_n!`](macro.log_every_n.html) at the `info` level.
+#[macro_export]
+macro_rules! info_every_n {
+	($n:expr, $($arg:tt)+) => ($crate::log_every_n!($crate::_log::Level::Info, $n, $($arg)+));
+}
+
+/// [`log_throttled!`](macro.log_throttled.html) at the `debug` level.
+#[macro_export]
+macro_rules! debug_throttled {
+	($period:expr, $($arg:tt)+) => ($crate::log_throttled!($crate::_log::Level::Debug, $period, $($arg)+));
+}
+
+/// [`log_once!`](macro.log_once.html) at the `debug` level.
+#[macro_export]
+macro_rules! debug_once {
+	($($arg:tt)+) => ($crate::log_once!($crate::_log::Level::Debug, $($arg)+));
+}
+
+/// [`log_every_n!`](macro.log_every_n.html) at the `debug` level.
+#[macro_export]
+macro_rules! debug_every_n {
+	($n:expr, $($arg:tt)+) => ($crate::log_every_n!($crate::_log::Level::Debug, $n, $($arg)+));
+}
\ No newline at end of file
@@ -0,0 +1,152 @@
+mod buffer;
+mod filter;
+mod forward;
+mod panic;
+#[doc(hidden)]
+pub mod limit;
+pub(crate) mod tag;
+
+pub use self::buffer::{LOG_BUFFER_SIZE, TRUNCATION_MARKER};
+pub use self::forward::{log_message, set_log_forwarding, LogForwarding};
+pub use self::panic::{panic_count, panic_report, set_panic_report, PanicReport, STORAGE_DIR};
+pub use self::tag::{log_tag, set_log_tag, LogTag};
+pub use self::filter::{
+	log_command, log_filter, set_log_filter, set_log_level_from_param, LogFilter, LogFilterError,
+	LOG_FILTER_ENV,
+};
+
+use log::{Metadata, Record};
+use std::fmt::Write;
+
+#[cfg(feature = "mock")]
+use crate::mock::log::{px4_log_modulename, px4_log_raw};
+
+#[cfg(not(feature = "mock"))]
+extern "C" {
+	fn px4_log_modulename(level: i32, module: *const u8, fmt: *const u8, ...);
+	fn px4_log_raw(level: i32, fmt: *const u8, ...);
+}
+
+/// The log level of a message, as used by PX4.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+	Debug = 0,
+	Info = 1,
+	Warn = 2,
+	Error = 3,
+	Panic = 4,
+}
+
+#[doc(hidden)]
+pub fn log_raw(level: LogLevel, message: &str) {
+	log_raw_bytes(level, message.as_bytes());
+}
+
+fn log_raw_bytes(level: LogLevel, message: &[u8]) {
+	unsafe {
+		px4_log_raw(
+			level as i32,
+			"%.*s\0".as_ptr(),
+			message.len() as i32,
+			message.as_ptr(),
+		);
+	}
+}
+
+/// Used by `info_raw!` to format output without allocating. Long output is
+/// passed to PX4 in multiple parts.
+#[doc(hidden)]
+pub fn log_raw_fmt(level: LogLevel, args: std::fmt::Arguments) {
+	if let Some(s) = args.as_str() {
+		return log_raw(level, s);
+	}
+	let mut w = buffer::Chunked::new(|chunk| log_raw_bytes(level, chunk));
+	let _ = w.write_fmt(args);
+	w.finish();
+}
+
+/// Print output without any decoration.
+///
+/// The equivalent of `PX4_INFO_RAW` in C and C++.
+///
+/// ## Example
+///
+/// ```
+/// # #[macro_use] extern crate px4;
+/// # #[no_mangle] fn px4_log_raw() {}
+/// # fn main() {
+/// info_raw!("Hello World!\n");
+/// info_raw!("Hello {}!\n", "World");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! info_raw {
+	($arg:expr) => (
+		$crate::log_raw($crate::LogLevel::Info, $arg)
+	);
+	($($arg:tt)+) => (
+		$crate::_log_raw_fmt($crate::LogLevel::Info, format_args!($($arg)+))
+	);
+}
+
+struct Px4Logger;
+
+impl log::Log for Px4Logger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= filter::current().level_for(metadata.target())
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.enabled(record.metadata()) {
+			return;
+		}
+
+		let level = match record.level() {
+			log::Level::Error => LogLevel::Error,
+			log::Level::Warn => LogLevel::Warn,
+			log::Level::Info => LogLevel::Info,
+			log::Level::Debug => LogLevel::Debug,
+			log::Level::Trace => LogLevel::Debug,
+		};
+
+		// Format both nul-terminated strings on the stack, such that logging
+		// doesn't allocate.
+		let mut module_buf = [0u8; buffer::MODULE_BUFFER_SIZE];
+		let mut message_buf = [0u8; buffer::LOG_BUFFER_SIZE];
+		let module = buffer::format_nul(
+			&mut module_buf,
+			format_args!("{}", tag::Display(Some(record.target()))),
+			"",
+		);
+		let message = buffer::format_nul(&mut message_buf, *record.args(), buffer::TRUNCATION_MARKER);
+
+		unsafe {
+			px4_log_modulename(
+				level as i32,
+				module.as_ptr(),
+				"%s\0".as_ptr(),
+				message.as_ptr(),
+			);
+		}
+
+		forward::forward(level, message);
+	}
+
+	fn flush(&self) {}
+}
+
+static LOGGER: Px4Logger = Px4Logger;
+
+/// Install the logger and panic hook, once, and use the name of the command
+/// as the tag of this thread, until the returned `Scope` is dropped.
+///
+/// Also reports the crash record of the previous run of the command.
+pub unsafe fn init(modulename: &'static [u8]) -> tag::Scope {
+	let scope = tag::enter(std::str::from_utf8(&modulename[..modulename.len() - 1]).unwrap_or("?"));
+	if log::set_logger(&LOGGER).is_ok() {
+		filter::init();
+		std::panic::set_hook(Box::new(panic::hook));
+	}
+	panic::check_previous_crash();
+	scope
+}
@@ -0,0 +1,184 @@
+use super::{buffer, tag, LogLevel};
+use std::backtrace::Backtrace;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, RwLock};
+
+/// The directory PX4 stores logs and other files in: the SD card on NuttX,
+/// and the working directory (the root file system of the simulation)
+/// otherwise.
+///
+/// The equivalent of `PX4_STORAGEDIR` in C and C++.
+#[cfg(target_os = "nuttx")]
+pub const STORAGE_DIR: &str = "/fs/microsd";
+#[cfg(not(target_os = "nuttx"))]
+pub const STORAGE_DIR: &str = ".";
+
+/// What to do when a panic occurs, besides logging it.
+///
+/// See [`set_panic_report`](fn.set_panic_report.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PanicReport {
+	/// Whether to log a backtrace. Backtraces only show function names when
+	/// the module is built with debug info.
+	pub backtrace: bool,
+	/// The directory to write a crash record to, named
+	/// `rust_panic_<module>.txt`, or `None` to not write one.
+	pub crash_dir: Option<PathBuf>,
+}
+
+/// Logs backtraces in debug builds, and writes crash records to
+/// [`STORAGE_DIR`](constant.STORAGE_DIR.html).
+///
+/// With the `mock` feature, neither is done by default.
+impl Default for PanicReport {
+	fn default() -> Self {
+		let mock = cfg!(feature = "mock");
+		PanicReport {
+			backtrace: cfg!(debug_assertions) && !mock,
+			crash_dir: if mock { None } else { Some(PathBuf::from(STORAGE_DIR)) },
+		}
+	}
+}
+
+impl PanicReport {
+	/// The path of the crash record for the module with the given name.
+	pub fn crash_file(&self, module: &str) -> Option<PathBuf> {
+		let dir = self.crash_dir.as_ref()?;
+		Some(dir.join(format!("rust_panic_{}.txt", module)))
+	}
+}
+
+static REPORT: RwLock<Option<PanicReport>> = RwLock::new(None);
+
+static PANIC_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// The crash records that were already looked for, or written by this run.
+static CHECKED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Set what to do when a panic occurs.
+///
+/// The crash record of the previous run is looked for when a command starts.
+/// When this is called from a command, and the `crash_dir` changed, it is
+/// also looked for in the new `crash_dir`.
+pub fn set_panic_report(report: PanicReport) {
+	let changed = {
+		let mut current = REPORT.write().unwrap_or_else(|e| e.into_inner());
+		let changed = current.as_ref().map_or(true, |r| r.crash_dir != report.crash_dir);
+		*current = Some(report);
+		changed
+	};
+	if changed {
+		check_previous_crash();
+	}
+}
+
+/// What is currently done when a panic occurs.
+pub fn panic_report() -> PanicReport {
+	REPORT.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+/// The number of panics since the module was loaded, in any thread.
+///
+/// Shown by the `status` command of a [`Module`](module/trait.Module.html).
+pub fn panic_count() -> u32 {
+	PANIC_COUNT.load(Ordering::Relaxed)
+}
+
+/// Report the crash record left by a previous run of the current command,
+/// if any, and rename it to `rust_panic_<module>.txt.old`, such that it is
+/// only reported once.
+pub(super) fn check_previous_crash() {
+	let file = match tag::current().and_then(|tag| panic_report().crash_file(&tag.name)) {
+		Some(file) => file,
+		None => return,
+	};
+	if !checked(&file) {
+		return;
+	}
+	let record = match std::fs::read_to_string(&file) {
+		Ok(record) => record,
+		Err(_) => return,
+	};
+	log::warn!(
+		target: "px4",
+		"previous run panicked: {}",
+		record.lines().next().unwrap_or("")
+	);
+	let mut old = file.clone().into_os_string();
+	old.push(".old");
+	if let Err(e) = std::fs::rename(&file, &old) {
+		log::warn!(target: "px4", "unable to rename {}: {}", file.display(), e);
+	}
+}
+
+/// Remember that the given crash record was looked for. Returns false if it
+/// already was.
+fn checked(file: &Path) -> bool {
+	let mut checked = CHECKED.lock().unwrap_or_else(|e| e.into_inner());
+	if checked.iter().any(|f| f == file) {
+		return false;
+	}
+	checked.push(file.to_path_buf());
+	true
+}
+
+fn write_crash_record(file: &Path, message: &str, backtrace: Option<&Backtrace>) -> std::io::Result<()> {
+	let mut record = format!("{}\ntime: {} us since boot\n", message, crate::hrt::absolute_time());
+	if let Some(backtrace) = backtrace {
+		let _ = write!(record, "backtrace:\n{}\n", backtrace);
+	}
+	std::fs::write(file, record)
+}
+
+pub(super) fn hook(info: &std::panic::PanicHookInfo) {
+	PANIC_COUNT.fetch_add(1, Ordering::Relaxed);
+	let report = panic_report();
+
+	let payload: &str = if let Some(s) = info.payload().downcast_ref::<&'static str>() {
+		s
+	} else if let Some(s) = info.payload().downcast_ref::<String>() {
+		s
+	} else {
+		"[unknown]"
+	};
+	let mut message = String::new();
+	let thread = std::thread::current();
+	if let Some(name) = thread.name() {
+		write!(message, "thread '{}' ", name).unwrap();
+	}
+	write!(message, "panicked at '{}'", payload).unwrap();
+	if let Some(loc) = info.location() {
+		write!(message, ", {}", loc).unwrap();
+	}
+
+	let mut module_buf = [0u8; buffer::MODULE_BUFFER_SIZE];
+	let module = buffer::format_nul(&mut module_buf, format_args!("{}", tag::Display(None)), "");
+	message.push('\0');
+	unsafe {
+		super::px4_log_modulename(
+			LogLevel::Panic as i32,
+			module.as_ptr(),
+			"%s\0".as_ptr(),
+			message.as_ptr(),
+		);
+	}
+	message.pop();
+
+	let backtrace = if report.backtrace {
+		let backtrace = Backtrace::force_capture();
+		super::log_raw_fmt(LogLevel::Error, format_args!("backtrace:\n{}\n", backtrace));
+		Some(backtrace)
+	} else {
+		None
+	};
+
+	if let Some(file) = report.crash_file(&tag::log_tag().name) {
+		// Not to be reported as a crash of a previous run.
+		checked(&file);
+		if let Err(e) = write_crash_record(&file, &message, backtrace.as_ref()) {
+			log::error!(target: "px4", "unable to write {}: {}", file.display(), e);
+		}
+	}
+}
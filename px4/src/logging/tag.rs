@@ -0,0 +1,137 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::RwLock;
+
+/// How the module name in front of every log message is formed.
+///
+/// PX4 shows it in brackets, like `[commander]`. By default, it is the name
+/// of the command: the name given to `#[px4_module_main(builtin = "...")]`,
+/// or otherwise the name of the crate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogTag {
+	/// The name of the module.
+	pub name: String,
+	/// Whether to append the target of the message (usually the Rust module
+	/// path it was logged from), as in `[name:my_crate::control]`.
+	///
+	/// The target is not repeated if it is the same as the name.
+	pub show_target: bool,
+}
+
+impl LogTag {
+	/// A tag with just the given name, without targets.
+	pub fn new(name: &str) -> Self {
+		LogTag {
+			name: name.to_string(),
+			show_target: false,
+		}
+	}
+
+	/// Write the tag for a message with the given target.
+	pub(super) fn write(&self, f: &mut dyn fmt::Write, target: Option<&str>) -> fmt::Result {
+		f.write_str(&self.name)?;
+		match target {
+			Some(target) if self.show_target && target != self.name => write!(f, ":{}", target),
+			_ => Ok(()),
+		}
+	}
+}
+
+/// Set by `set_log_tag` outside of any command, for all commands run after it.
+static TAG: RwLock<Option<LogTag>> = RwLock::new(None);
+
+/// The tag of the first command, for threads not started by any command.
+static FIRST: RwLock<Option<LogTag>> = RwLock::new(None);
+
+thread_local! {
+	/// The tag of the command this thread runs for.
+	static CURRENT: RefCell<Option<LogTag>> = const { RefCell::new(None) };
+}
+
+/// Set the module name shown in front of log messages, including panics.
+///
+/// Called from a command, or a task it spawned with
+/// [`task::spawn`](task/fn.spawn.html), this only changes the tag of that
+/// command. Otherwise, it sets the tag of all commands that are run after it.
+pub fn set_log_tag(tag: LogTag) {
+	let tag = CURRENT.with(|current| match &mut *current.borrow_mut() {
+		Some(current) => {
+			*current = tag;
+			None
+		}
+		None => Some(tag),
+	});
+	if let Some(tag) = tag {
+		*TAG.write().unwrap_or_else(|e| e.into_inner()) = Some(tag);
+	}
+}
+
+/// The module name currently shown in front of log messages.
+pub fn log_tag() -> LogTag {
+	current().unwrap_or_else(|| LogTag::new(""))
+}
+
+/// The tag of the command this thread runs for, or otherwise the one set
+/// for all commands, or otherwise the tag of the first command.
+pub(crate) fn current() -> Option<LogTag> {
+	with_current(|tag| tag.cloned())
+}
+
+/// Like `current`, without cloning the tag, such that logging doesn't allocate.
+fn with_current<R>(f: impl FnOnce(Option<&LogTag>) -> R) -> R {
+	CURRENT.with(|current| {
+		if let Some(tag) = &*current.borrow() {
+			return f(Some(tag));
+		}
+		let tag = TAG.read().unwrap_or_else(|e| e.into_inner());
+		if tag.is_some() {
+			return f(tag.as_ref());
+		}
+		drop(tag);
+		f(FIRST.read().unwrap_or_else(|e| e.into_inner()).as_ref())
+	})
+}
+
+/// Formats the tag for a message with the given target.
+pub(super) struct Display<'a>(pub Option<&'a str>);
+
+impl fmt::Display for Display<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		with_current(|tag| match tag {
+			Some(tag) => tag.write(f, self.0),
+			None => f.write_str(self.0.unwrap_or("")),
+		})
+	}
+}
+
+/// Restores the previous tag of the thread when dropped.
+pub(crate) struct Scope(Option<LogTag>);
+
+impl Drop for Scope {
+	fn drop(&mut self) {
+		let previous = self.0.take();
+		CURRENT.with(|current| *current.borrow_mut() = previous);
+	}
+}
+
+/// Use the given tag for this thread, until the returned `Scope` is dropped.
+pub(crate) fn scope(tag: Option<LogTag>) -> Scope {
+	Scope(CURRENT.with(|current| current.replace(tag)))
+}
+
+/// Use the name of the command as the tag of this thread, unless one was set
+/// for all commands.
+///
+/// `modulename` is the name of the builtin command, or the module path of
+/// the main function, of which only the crate name is used.
+pub(super) fn enter(modulename: &str) -> Scope {
+	let name = modulename.split("::").next().unwrap_or(modulename);
+	let tag = TAG.read().unwrap_or_else(|e| e.into_inner()).clone();
+	let tag = tag.unwrap_or_else(|| LogTag::new(name));
+	let mut first = FIRST.write().unwrap_or_else(|e| e.into_inner());
+	if first.is_none() {
+		*first = Some(tag.clone());
+	}
+	drop(first);
+	scope(Some(tag))
+}
@@ -0,0 +1,85 @@
+//! Picks the way the return value of a `#[px4_module_main]` function is
+//! turned into a status code.
+//!
+//! This uses 'autoref specialization': the macro calls `__px4_status` on
+//! `&&&&Wrap<R>`, and method resolution picks the first trait below that is
+//! implemented, taking one reference off at each step. That way, errors are
+//! only logged with `Debug` if they don't implement `Display`, and a
+//! `MainStatusCode` implementation always wins.
+
+use crate::{ErrorStatusCode, MainStatusCode};
+use log::error;
+use std::cell::Cell;
+use std::fmt::{Debug, Display};
+
+pub struct Wrap<R>(Cell<Option<R>>);
+
+impl<R> Wrap<R> {
+	pub fn new(value: R) -> Self {
+		Wrap(Cell::new(Some(value)))
+	}
+
+	fn take(&self) -> R {
+		self.0.take().expect("status code already taken")
+	}
+}
+
+pub trait ViaMainStatusCode {
+	type Output: MainStatusCode;
+	fn __px4_status(self, target: &str) -> Self::Output;
+}
+
+impl<R: MainStatusCode> ViaMainStatusCode for &&&&Wrap<R> {
+	type Output = R;
+	fn __px4_status(self, _target: &str) -> R {
+		self.take()
+	}
+}
+
+pub trait ViaErrorStatusCode {
+	fn __px4_status(self, target: &str) -> i32;
+}
+
+impl<T: MainStatusCode, E: ErrorStatusCode> ViaErrorStatusCode for &&&Wrap<Result<T, E>> {
+	fn __px4_status(self, target: &str) -> i32 {
+		match self.take() {
+			Ok(v) => v.to_status_code(),
+			Err(e) => {
+				error!(target: target, "{}", e);
+				e.status_code()
+			}
+		}
+	}
+}
+
+pub trait ViaDisplay {
+	fn __px4_status(self, target: &str) -> i32;
+}
+
+impl<T: MainStatusCode, E: Display> ViaDisplay for &&Wrap<Result<T, E>> {
+	fn __px4_status(self, target: &str) -> i32 {
+		match self.take() {
+			Ok(v) => v.to_status_code(),
+			Err(e) => {
+				error!(target: target, "{}", e);
+				1
+			}
+		}
+	}
+}
+
+pub trait ViaDebug {
+	fn __px4_status(self, target: &str) -> i32;
+}
+
+impl<T: MainStatusCode, E: Debug> ViaDebug for &Wrap<Result<T, E>> {
+	fn __px4_status(self, target: &str) -> i32 {
+		match self.take() {
+			Ok(v) => v.to_status_code(),
+			Err(e) => {
+				error!(target: target, "{:?}", e);
+				1
+			}
+		}
+	}
+}
@@ -0,0 +1,33 @@
+//! A stand-in for the high-resolution timer of PX4.
+//!
+//! The clock starts at zero when it is first used, and runs at the speed of
+//! the wall clock. It can be moved forward with
+//! [`advance`](fn.advance.html) to simulate the passing of time.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static START: OnceLock<Instant> = OnceLock::new();
+static OFFSET: AtomicU64 = AtomicU64::new(0);
+
+/// Move the clock forward by the given duration.
+pub fn advance(duration: Duration) {
+	OFFSET.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+}
+
+/// # Safety
+///
+/// Always safe to call. Unsafe to match the C function.
+pub unsafe extern "C" fn hrt_absolute_time() -> u64 {
+	let elapsed = START.get_or_init(Instant::now).elapsed();
+	elapsed.as_micros() as u64 + OFFSET.load(Ordering::Relaxed)
+}
+
+/// # Safety
+///
+/// Always safe to call. Unsafe to match the C function.
+pub unsafe extern "C" fn px4_usleep(usec: u32) -> i32 {
+	std::thread::sleep(Duration::from_micros(u64::from(usec)));
+	0
+}
@@ -0,0 +1,104 @@
+//! A stand-in for the logging functions of PX4.
+//!
+//! Messages are written to standard error, unless they are captured by
+//! [`run_module`](../fn.run_module.html) or discarded with
+//! [`set_discard`](fn.set_discard.html).
+
+#![allow(clippy::missing_safety_doc)]
+
+use crate::LogLevel;
+use std::ffi::CStr;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// A message logged through PX4.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogRecord {
+	/// The log level.
+	pub level: LogLevel,
+	/// The module name, or `None` for raw output (e.g. from `info_raw!`).
+	pub module: Option<String>,
+	/// The message.
+	pub message: String,
+}
+
+static CAPTURE: Mutex<Option<Vec<LogRecord>>> = Mutex::new(None);
+
+fn capture() -> MutexGuard<'static, Option<Vec<LogRecord>>> {
+	CAPTURE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn start_capture() {
+	*capture() = Some(Vec::new());
+}
+
+pub(crate) fn stop_capture() -> Vec<LogRecord> {
+	capture().take().unwrap_or_default()
+}
+
+static DISCARD: AtomicBool = AtomicBool::new(false);
+
+/// Drop all messages which are not captured, instead of writing them to
+/// standard error.
+///
+/// Dropped messages are not even copied, such that the logging functions
+/// don't allocate. Useful for benchmarks.
+pub fn set_discard(discard: bool) {
+	DISCARD.store(discard, Ordering::Relaxed);
+}
+
+fn discarded() -> bool {
+	DISCARD.load(Ordering::Relaxed) && capture().is_none()
+}
+
+fn record(record: LogRecord) {
+	if let Some(records) = &mut *capture() {
+		records.push(record);
+		return;
+	}
+	let mut stderr = std::io::stderr();
+	let _ = match &record.module {
+		Some(module) => writeln!(stderr, "{:?} [{}] {}", record.level, module, record.message),
+		None => write!(stderr, "{}", record.message),
+	};
+}
+
+fn level(level: i32) -> LogLevel {
+	match level {
+		0 => LogLevel::Debug,
+		1 => LogLevel::Info,
+		2 => LogLevel::Warn,
+		3 => LogLevel::Error,
+		_ => LogLevel::Panic,
+	}
+}
+
+/// Equivalent to `px4_log_modulename(level, module, "%s", message)`.
+///
+/// (The C function is variadic, but is always called with these arguments.)
+pub unsafe extern "C" fn px4_log_modulename(level: i32, module: *const u8, _fmt: *const u8, message: *const u8) {
+	if discarded() {
+		return;
+	}
+	record(LogRecord {
+		level: self::level(level),
+		module: Some(CStr::from_ptr(module as _).to_string_lossy().into_owned()),
+		message: CStr::from_ptr(message as _).to_string_lossy().into_owned(),
+	});
+}
+
+/// Equivalent to `px4_log_raw(level, "%.*s", len, message)`.
+///
+/// (The C function is variadic, but is always called with these arguments.)
+pub unsafe extern "C" fn px4_log_raw(level: i32, _fmt: *const u8, len: i32, message: *const u8) {
+	if discarded() {
+		return;
+	}
+	let message = std::slice::from_raw_parts(message, len as usize);
+	record(LogRecord {
+		level: self::level(level),
+		module: None,
+		message: String::from_utf8_lossy(message).into_owned(),
+	});
+}
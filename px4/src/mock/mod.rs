@@ -0,0 +1,94 @@
+//! In-process implementations of the PX4 C functions used by this crate.
+//!
+//! When the `mock` feature is enabled, the bindings in this crate call these
+//! functions instead of the ones exported by PX4. This allows code using
+//! [`uorb`](../uorb/index.html), [`hrt`](../hrt/index.html),
+//! [`task`](../task/index.html), [`param`](../param/index.html) and logging
+//! to run in a plain `cargo test` on a development machine, without PX4:
+//!
+//! ```text
+//! [dev-dependencies]
+//! px4 = { version = "*", features = ["mock"] }
+//! ```
+//!
+//! The functions have the same signatures as their C counterparts, so they
+//! can also be used to implement the C ABI of PX4 outside of PX4 itself.
+//!
+//! All state is global to the process. Tests running in parallel in the same
+//! process see each other's topics, so use a different message per test.
+//!
+//! ## Testing a module
+//!
+//! [`run_module`](fn.run_module.html) runs the entry point generated by
+//! `#[px4_module_main]`, and captures everything it logs:
+//!
+//! ```ignore
+//! #[test]
+//! fn hello() {
+//!   let output = px4::mock::run_module(px4_module_main, &["hello", "--name", "World"]);
+//!   assert_eq!(output.status, 0);
+//!   assert_eq!(output.records[0].message, "Hello World!");
+//! }
+//! ```
+
+pub mod hrt;
+pub mod log;
+pub mod param;
+pub mod shutdown;
+pub mod task;
+pub mod uorb;
+
+pub use self::log::LogRecord;
+
+use std::ffi::{CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::sync::Mutex;
+
+/// The result of [`run_module`](fn.run_module.html).
+#[derive(Clone, Debug)]
+pub struct ModuleOutput {
+	/// The status code returned by the module.
+	pub status: i32,
+	/// Everything logged while the module was running, including raw output
+	/// and panics.
+	pub records: Vec<LogRecord>,
+}
+
+impl ModuleOutput {
+	/// All raw output (e.g. from `info_raw!`) concatenated.
+	pub fn raw_output(&self) -> String {
+		self.records
+			.iter()
+			.filter(|r| r.module.is_none())
+			.map(|r| &r.message[..])
+			.collect()
+	}
+}
+
+/// Run the entry point of a module, with the given arguments.
+///
+/// `main` is the `px4_module_main` function generated by `#[px4_module_main]`.
+/// Like in PX4, the first argument is the name of the module. Arguments can
+/// be given as `&str` or as `&OsStr`, to test arguments that are not valid
+/// UTF-8.
+///
+/// All messages logged while the module runs are captured, including those
+/// logged by other threads. Only one module runs at a time: Concurrent calls
+/// wait for each other.
+pub fn run_module<A: AsRef<OsStr>>(
+	main: extern "C" fn(u32, *mut *mut u8) -> i32,
+	args: &[A],
+) -> ModuleOutput {
+	static RUNNING: Mutex<()> = Mutex::new(());
+	let _running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+	let args: Vec<CString> = args
+		.iter()
+		.map(|a| CString::new(a.as_ref().as_bytes()).expect("nul byte in argument"))
+		.collect();
+	let mut argv: Vec<*mut u8> = args.iter().map(|a| a.as_ptr() as *mut u8).collect();
+	argv.push(std::ptr::null_mut());
+	self::log::start_capture();
+	let status = main(args.len() as u32, argv.as_mut_ptr());
+	let records = self::log::stop_capture();
+	ModuleOutput { status, records }
+}
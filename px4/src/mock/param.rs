@@ -0,0 +1,95 @@
+//! A stand-in for the parameter system of PX4.
+//!
+//! Parameters don't exist until defined with [`define`](fn.define.html) or
+//! [`define_defaults`](fn.define_defaults.html), like parameters in PX4 need
+//! to be defined in the firmware.
+//!
+//! Like in PX4, setting a parameter publishes a `parameter_update` message.
+
+#![allow(clippy::missing_safety_doc)]
+
+pub use crate::param::Value;
+
+use crate::param::{parameter_update, Definition, ParamType, PARAM_INVALID};
+use crate::uorb::{Publish, Publisher};
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_char;
+use std::sync::{Mutex, MutexGuard};
+
+static PARAMS: Mutex<Vec<(String, Value)>> = Mutex::new(Vec::new());
+
+fn params() -> MutexGuard<'static, Vec<(String, Value)>> {
+	PARAMS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Define a parameter, or change the value (and type) of an existing one.
+pub fn define(name: &str, value: impl Into<Value>) {
+	let value = value.into();
+	let mut params = params();
+	match params.iter_mut().find(|p| p.0 == name) {
+		Some(p) => p.1 = value,
+		None => params.push((name.to_string(), value)),
+	}
+}
+
+/// Define the given parameters with their default values, like PX4 does
+/// for the parameters defined in the firmware.
+///
+/// Parameters which are already defined keep their value.
+pub fn define_defaults(definitions: &[Definition]) {
+	let mut params = params();
+	for d in definitions {
+		if !params.iter().any(|p| p.0 == d.name) {
+			params.push((d.name.to_string(), d.default));
+		}
+	}
+}
+
+/// Get the value of a parameter, if it exists.
+pub fn value(name: &str) -> Option<Value> {
+	params().iter().find(|p| p.0 == name).map(|p| p.1)
+}
+
+pub unsafe extern "C" fn param_find(name: *const c_char) -> u16 {
+	let name = CStr::from_ptr(name).to_bytes();
+	params()
+		.iter()
+		.position(|p| p.0.as_bytes() == name)
+		.map_or(PARAM_INVALID, |i| i as u16)
+}
+
+pub unsafe extern "C" fn param_type(param: u16) -> u8 {
+	match params().get(param as usize) {
+		Some((_, Value::Int32(_))) => ParamType::Int32.to_raw(),
+		Some((_, Value::Float(_))) => ParamType::Float.to_raw(),
+		None => ParamType::Other(0).to_raw(),
+	}
+}
+
+pub unsafe extern "C" fn param_get(param: u16, val: *mut c_void) -> i32 {
+	match params().get(param as usize) {
+		Some((_, Value::Int32(v))) => *(val as *mut i32) = *v,
+		Some((_, Value::Float(v))) => *(val as *mut f32) = *v,
+		None => return -1,
+	}
+	0
+}
+
+pub unsafe extern "C" fn param_set(param: u16, val: *const c_void) -> i32 {
+	match params().get_mut(param as usize) {
+		Some((_, Value::Int32(v))) => *v = *(val as *const i32),
+		Some((_, Value::Float(v))) => *v = *(val as *const f32),
+		None => return -1,
+	}
+	param_notify_changes();
+	0
+}
+
+pub unsafe extern "C" fn param_notify_changes() {
+	static UPDATES: Mutex<(Option<Publisher<parameter_update>>, u32)> = Mutex::new((None, 0));
+	let mut updates = UPDATES.lock().unwrap_or_else(|e| e.into_inner());
+	let (publisher, instance) = &mut *updates;
+	*instance += 1;
+	let publisher = publisher.get_or_insert_with(parameter_update::advertise);
+	let _ = publisher.publish_now(&mut parameter_update::new(*instance));
+}
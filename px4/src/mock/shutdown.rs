@@ -0,0 +1,36 @@
+//! A stand-in for the shutdown functions of PX4.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static HOOKS: Mutex<Vec<extern "C" fn() -> bool>> = Mutex::new(Vec::new());
+
+pub unsafe extern "C" fn px4_register_shutdown_hook(hook: extern "C" fn() -> bool) -> i32 {
+	HOOKS.lock().unwrap_or_else(|e| e.into_inner()).push(hook);
+	0
+}
+
+/// How long PX4 keeps calling the shutdown hooks until they are all ready.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Call all registered shutdown hooks, as PX4 does when it shuts down: every
+/// 10 ms, until they all report to be ready for shutdown, or until 5 seconds
+/// have passed.
+///
+/// Returns whether all hooks reported to be ready for shutdown.
+pub fn shutdown() -> bool {
+	let hooks = HOOKS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+	let start = Instant::now();
+	loop {
+		let mut ready = true;
+		for &hook in &hooks {
+			ready &= hook();
+		}
+		if ready || start.elapsed() >= TIMEOUT {
+			return ready;
+		}
+		std::thread::sleep(Duration::from_millis(10));
+	}
+}
@@ -0,0 +1,56 @@
+//! A stand-in for the task functions of PX4.
+//!
+//! Tasks are run as normal threads. The priority and stack size are ignored.
+//! The status code returned by a task can be checked with
+//! [`exit_status`](fn.exit_status.html).
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Mutex;
+
+static NEXT_ID: AtomicI32 = AtomicI32::new(1);
+
+/// The ids and status codes of finished tasks.
+static EXIT_STATUS: Mutex<Vec<(i32, i32)>> = Mutex::new(Vec::new());
+
+/// The status code returned by the entry point of a task, or `None` if it
+/// is still running.
+pub fn exit_status(id: i32) -> Option<i32> {
+	let exit_status = EXIT_STATUS.lock().unwrap_or_else(|e| e.into_inner());
+	exit_status.iter().find(|&&(i, _)| i == id).map(|&(_, status)| status)
+}
+
+pub unsafe extern "C" fn px4_task_spawn_cmd(
+	name: *const c_char,
+	_scheduler: i32,
+	_priority: i32,
+	_stack_size: i32,
+	entry: unsafe extern "C" fn(i32, *mut *mut c_char) -> i32,
+	argv: *const *const c_char,
+) -> i32 {
+	// Like PX4, copy the arguments, with the name as the first argument.
+	let name = CStr::from_ptr(name).to_owned();
+	let mut args = vec![name.clone()];
+	let mut arg = argv;
+	while !(*arg).is_null() {
+		args.push(CStr::from_ptr(*arg).to_owned());
+		arg = arg.add(1);
+	}
+	let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+	let thread = std::thread::Builder::new()
+		.name(name.to_string_lossy().into_owned())
+		.spawn(move || {
+			let mut argv: Vec<*mut c_char> = args.iter().map(|a| a.as_ptr() as *mut c_char).collect();
+			argv.push(std::ptr::null_mut());
+			let status = entry(args.len() as i32, argv.as_mut_ptr());
+			drop::<Vec<CString>>(args);
+			EXIT_STATUS.lock().unwrap_or_else(|e| e.into_inner()).push((id, status));
+		});
+	match thread {
+		Ok(_) => id,
+		Err(_) => -1,
+	}
+}
@@ -0,0 +1,398 @@
+//! A stand-in for the uORB message broker.
+//!
+//! Topics are identified by the name in their [`Metadata`], so messages
+//! defined in different binaries (e.g. a dynamically loaded module) still
+//! end up on the same topic.
+//!
+//! Like in PX4, every topic can have up to
+//! [`MAX_INSTANCES`](constant.MAX_INSTANCES.html) instances, each with its
+//! own priority and queue. A subscription sees every queued message once
+//! through `orb_copy`, and `orb_check` reports whether there are any unseen
+//! messages, taking the interval set by `orb_set_interval` into account.
+//!
+//! Like in PX4 v1.12 and later, the size of a topic is the size in the
+//! metadata it was first advertised with (or, until then, first subscribed
+//! with). `orb_copy` always copies that many bytes, even when the metadata
+//! given to it is smaller.
+//!
+//! # Safety
+//!
+//! All functions have the same safety requirements as their C counterparts:
+//! The pointers must be valid, and data buffers must be as large as the size
+//! of the topic.
+//!
+//! [`Metadata`]: ../../uorb/struct.Metadata.html
+
+#![allow(clippy::missing_safety_doc)]
+
+use super::hrt::hrt_absolute_time;
+use crate::uorb::{priority, Metadata, PollFd};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::ptr::copy_nonoverlapping;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// The maximum number of instances of a topic.
+///
+/// Equivalent to `ORB_MULTI_MAX_INSTANCES` in C and C++.
+pub const MAX_INSTANCES: u32 = 4;
+
+const ENOENT: i32 = 2;
+const EEXIST: i32 = 17;
+const EINVAL: i32 = 22;
+
+struct Node {
+	name: String,
+	instance: u32,
+	size: usize,
+	advertised: bool,
+	priority: i32,
+	queue_size: usize,
+	queue: VecDeque<Vec<u8>>,
+	generation: u64,
+	last_update: u64,
+}
+
+impl Node {
+	fn publish(&mut self, data: *const u8) {
+		let mut message = vec![0u8; self.size];
+		unsafe { copy_nonoverlapping(data, message.as_mut_ptr(), self.size) };
+		if self.queue.len() >= self.queue_size {
+			self.queue.pop_front();
+		}
+		self.queue.push_back(message);
+		self.generation += 1;
+		self.last_update = unsafe { hrt_absolute_time() };
+		PUBLISHED.notify_all();
+	}
+
+	fn oldest_generation(&self) -> u64 {
+		self.generation - self.queue.len() as u64
+	}
+}
+
+struct Subscriber {
+	node: usize,
+	generation: u64,
+	interval_ms: u32,
+	last_copy: u64,
+}
+
+struct Broker {
+	nodes: Vec<Node>,
+	subscribers: Vec<Option<Subscriber>>,
+}
+
+static BROKER: Mutex<Broker> = Mutex::new(Broker {
+	nodes: Vec::new(),
+	subscribers: Vec::new(),
+});
+
+/// Notified whenever a message is published, for `px4_poll`.
+static PUBLISHED: Condvar = Condvar::new();
+
+thread_local! {
+	static ERRNO: Cell<i32> = const { Cell::new(0) };
+}
+
+fn broker() -> MutexGuard<'static, Broker> {
+	BROKER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn fail<T>(errno: i32, result: T) -> T {
+	set_errno(errno);
+	result
+}
+
+impl Broker {
+	fn find(&self, meta: &Metadata, instance: u32) -> Option<usize> {
+		self.nodes
+			.iter()
+			.position(|n| n.instance == instance && n.name == meta.name())
+	}
+
+	fn find_or_create(&mut self, meta: &Metadata, instance: u32) -> usize {
+		if let Some(i) = self.find(meta, instance) {
+			return i;
+		}
+		self.nodes.push(Node {
+			name: meta.name().to_string(),
+			instance,
+			size: meta.size() as usize,
+			advertised: false,
+			priority: priority::DEFAULT,
+			queue_size: 1,
+			queue: VecDeque::new(),
+			generation: 0,
+			last_update: 0,
+		});
+		self.nodes.len() - 1
+	}
+
+	fn subscribe(&mut self, meta: &Metadata, instance: u32) -> i32 {
+		if instance >= MAX_INSTANCES {
+			return fail(EINVAL, -1);
+		}
+		let node = self.find_or_create(meta, instance);
+		let n = &self.nodes[node];
+		let subscriber = Subscriber {
+			node,
+			// Like in PX4, messages that are still queued can be read by a
+			// new subscriber.
+			generation: n.oldest_generation(),
+			interval_ms: 0,
+			last_copy: 0,
+		};
+		match self.subscribers.iter().position(Option::is_none) {
+			Some(fd) => {
+				self.subscribers[fd] = Some(subscriber);
+				fd as i32
+			}
+			None => {
+				self.subscribers.push(Some(subscriber));
+				self.subscribers.len() as i32 - 1
+			}
+		}
+	}
+
+	fn subscriber(&mut self, handle: i32) -> Option<&mut Subscriber> {
+		if handle < 0 {
+			return None;
+		}
+		self.subscribers.get_mut(handle as usize)?.as_mut()
+	}
+
+	/// Whether the subscription has unseen messages, and whether its interval
+	/// has passed.
+	fn pending(&mut self, handle: i32) -> Option<(bool, bool)> {
+		let (sub, node) = self.subscriber_and_node(handle)?;
+		let unseen = sub.generation < node.generation;
+		let interval_passed = sub.last_copy == 0
+			|| unsafe { hrt_absolute_time() } >= sub.last_copy + u64::from(sub.interval_ms) * 1000;
+		Some((unseen, interval_passed))
+	}
+
+	fn subscriber_and_node(&mut self, handle: i32) -> Option<(&mut Subscriber, &Node)> {
+		if handle < 0 {
+			return None;
+		}
+		let sub = self.subscribers.get_mut(handle as usize)?.as_mut()?;
+		let node = &self.nodes[sub.node];
+		Some((sub, node))
+	}
+}
+
+pub fn errno() -> i32 {
+	ERRNO.with(|e| e.get())
+}
+
+pub fn set_errno(errno: i32) {
+	ERRNO.with(|e| e.set(errno));
+}
+
+pub unsafe extern "C" fn orb_advertise_multi_queue(
+	meta: *const Metadata,
+	data: *const u8,
+	instance: *mut i32,
+	priority: i32,
+	queue_size: u32,
+) -> usize {
+	let meta = &*meta;
+	let mut broker = broker();
+	let node = if instance.is_null() {
+		broker.find_or_create(meta, 0)
+	} else {
+		let broker = &mut *broker;
+		let nodes = &broker.nodes;
+		let free = (0..MAX_INSTANCES)
+			.find(|&i| broker.find(meta, i).map_or(true, |n| !nodes[n].advertised));
+		match free {
+			Some(i) => {
+				*instance = i as i32;
+				broker.find_or_create(meta, i)
+			}
+			None => return fail(EEXIST, 0),
+		}
+	};
+	let n = &mut broker.nodes[node];
+	if !n.advertised {
+		if n.generation == 0 {
+			n.size = meta.size() as usize;
+		}
+		n.advertised = true;
+		n.priority = priority;
+		n.queue_size = queue_size.max(1) as usize;
+	}
+	if !data.is_null() {
+		n.publish(data);
+	}
+	// Handles start at 1, since 0 indicates an error.
+	node + 1
+}
+
+pub unsafe extern "C" fn orb_unadvertise(handle: usize) -> i32 {
+	match broker().nodes.get_mut(handle.wrapping_sub(1)) {
+		Some(node) => {
+			node.advertised = false;
+			0
+		}
+		None => fail(EINVAL, -1),
+	}
+}
+
+pub unsafe extern "C" fn orb_publish(meta: *const Metadata, handle: usize, data: *const u8) -> i32 {
+	match broker().nodes.get_mut(handle.wrapping_sub(1)) {
+		Some(node) if node.name == (*meta).name() => {
+			node.publish(data);
+			0
+		}
+		_ => fail(EINVAL, -1),
+	}
+}
+
+pub unsafe extern "C" fn orb_subscribe(meta: *const Metadata) -> i32 {
+	broker().subscribe(&*meta, 0)
+}
+
+pub unsafe extern "C" fn orb_subscribe_multi(meta: *const Metadata, instance: u32) -> i32 {
+	broker().subscribe(&*meta, instance)
+}
+
+pub unsafe extern "C" fn orb_unsubscribe(handle: i32) -> i32 {
+	let mut broker = broker();
+	if broker.subscriber(handle).is_none() {
+		return fail(EINVAL, -1);
+	}
+	broker.subscribers[handle as usize] = None;
+	0
+}
+
+pub unsafe extern "C" fn orb_copy(meta: *const Metadata, handle: i32, buffer: *mut u8) -> i32 {
+	let mut broker = broker();
+	let (sub, node) = match broker.subscriber_and_node(handle) {
+		Some((sub, node)) if node.name == (*meta).name() => (sub, node),
+		_ => return fail(EINVAL, -1),
+	};
+	let oldest = node.oldest_generation();
+	let message = if sub.generation < oldest {
+		sub.generation = oldest + 1;
+		node.queue.front()
+	} else if sub.generation < node.generation {
+		sub.generation += 1;
+		node.queue.get((sub.generation - 1 - oldest) as usize)
+	} else {
+		node.queue.back()
+	};
+	match message {
+		Some(message) => {
+			copy_nonoverlapping(message.as_ptr(), buffer, node.size);
+			sub.last_copy = hrt_absolute_time();
+			0
+		}
+		None => fail(ENOENT, -1),
+	}
+}
+
+pub unsafe extern "C" fn orb_check(handle: i32, updated: *mut bool) -> i32 {
+	match broker().pending(handle) {
+		Some((unseen, interval_passed)) => {
+			*updated = unseen && interval_passed;
+			0
+		}
+		None => fail(EINVAL, -1),
+	}
+}
+
+/// Waits until any of the subscriptions would be reported as updated by
+/// `orb_check`, or until `timeout` milliseconds passed.
+///
+/// Returns the number of updated subscriptions. `revents` is not set.
+pub unsafe extern "C" fn px4_poll(fds: *mut PollFd, nfds: u32, timeout: i32) -> i32 {
+	let fds = std::slice::from_raw_parts(fds, nfds as usize);
+	let end = Instant::now() + Duration::from_millis(timeout.max(0) as u64);
+	let mut broker = broker();
+	loop {
+		let mut ready = 0;
+		let mut waiting_for_interval = false;
+		for fd in fds {
+			match broker.pending(fd.fd) {
+				Some((true, true)) => ready += 1,
+				Some((true, false)) => waiting_for_interval = true,
+				Some(_) => {}
+				None => return fail(EINVAL, -1),
+			}
+		}
+		let now = Instant::now();
+		if ready > 0 || now >= end {
+			return ready;
+		}
+		let mut wait = end - now;
+		if waiting_for_interval {
+			// The interval passing doesn't notify anything.
+			wait = wait.min(Duration::from_millis(1));
+		}
+		broker = PUBLISHED
+			.wait_timeout(broker, wait)
+			.unwrap_or_else(|e| e.into_inner())
+			.0;
+	}
+}
+
+pub unsafe extern "C" fn orb_stat(handle: i32, time: *mut u64) -> i32 {
+	match broker().subscriber_and_node(handle) {
+		Some((_, node)) => {
+			*time = node.last_update;
+			0
+		}
+		None => fail(EINVAL, -1),
+	}
+}
+
+pub unsafe extern "C" fn orb_exists(meta: *const Metadata, instance: i32) -> i32 {
+	let broker = broker();
+	match broker.find(&*meta, instance as u32) {
+		Some(node) if broker.nodes[node].advertised => 0,
+		_ => fail(ENOENT, -1),
+	}
+}
+
+pub unsafe extern "C" fn orb_group_count(meta: *const Metadata) -> i32 {
+	let broker = broker();
+	let meta = &*meta;
+	broker
+		.nodes
+		.iter()
+		.filter(|n| n.advertised && n.name == meta.name())
+		.count() as i32
+}
+
+pub unsafe extern "C" fn orb_priority(handle: i32, priority: *mut i32) -> i32 {
+	match broker().subscriber_and_node(handle) {
+		Some((_, node)) => {
+			*priority = node.priority;
+			0
+		}
+		None => fail(EINVAL, -1),
+	}
+}
+
+pub unsafe extern "C" fn orb_set_interval(handle: i32, interval: u32) -> i32 {
+	match broker().subscriber(handle) {
+		Some(sub) => {
+			sub.interval_ms = interval;
+			0
+		}
+		None => fail(EINVAL, -1),
+	}
+}
+
+pub unsafe extern "C" fn orb_get_interval(handle: i32, interval: *mut u32) -> i32 {
+	match broker().subscriber(handle) {
+		Some(sub) => {
+			*interval = sub.interval_ms;
+			0
+		}
+		None => fail(EINVAL, -1),
+	}
+}
@@ -0,0 +1,267 @@
+//! Long-running modules with `start`, `stop` and `status` commands.
+//!
+//! Most PX4 modules don't do their work in their main function, but start a
+//! task that keeps running in the background. In C++, this is done through
+//! `ModuleBase`, which provides the `start`, `stop` and `status` commands.
+//!
+//! In Rust, implement the [`Module`](trait.Module.html) trait, and call
+//! [`main`](fn.main.html) from your `#[px4_module_main]` function:
+//!
+//! ```
+//! use log::info;
+//! use px4::{px4_module_main, ShouldExit};
+//! use px4::module::Module;
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//! use std::time::Duration;
+//!
+//! struct Counter {
+//!   count: AtomicU64,
+//! }
+//!
+//! impl Module for Counter {
+//!   const NAME: &'static str = "counter";
+//!
+//!   fn instantiate(_args: &[&str]) -> Result<Self, i32> {
+//!     Ok(Counter { count: AtomicU64::new(0) })
+//!   }
+//!
+//!   fn run(&self, should_exit: &ShouldExit) {
+//!     while !should_exit.sleep(Duration::from_millis(100)) {
+//!       self.count.fetch_add(1, Ordering::Relaxed);
+//!     }
+//!   }
+//!
+//!   fn print_status(&self) {
+//!     info!("Counted to {}", self.count.load(Ordering::Relaxed));
+//!   }
+//! }
+//!
+//! #[px4_module_main]
+//! fn counter_main(args: &[&str]) -> i32 {
+//!   px4::module::main::<Counter>(args)
+//! }
+//! ```
+//!
+//! The module can then be used as `counter start`, `counter status` and
+//! `counter stop`. Only one instance of a module can run at a time.
+//!
+//! The `stop` command sets the [`ShouldExit`](../struct.ShouldExit.html)
+//! token given to [`run`](trait.Module.html#tymethod.run), as does PX4 when
+//! it shuts down. Use [`ShouldExit::sleep`](../struct.ShouldExit.html#method.sleep)
+//! and [`Subscription::wait_or_exit`](../uorb/struct.Subscription.html#method.wait_or_exit)
+//! to make sure the task reacts promptly.
+//!
+//! The `log` command shows or changes the [log filter](../struct.LogFilter.html)
+//! while the module runs, e.g. `counter log debug`.
+//!
+//! Note that the library containing the module stays loaded while the module
+//! is running. (The `dyn` command never unloads libraries.)
+
+use crate::task::{self, Task};
+use crate::usage::Usage;
+use crate::{info_raw, ShouldExit};
+use log::{error, info, warn};
+use std::any::{Any, TypeId};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How long `stop` waits for the module to exit.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A module which runs in the background.
+///
+/// See the [module documentation](index.html).
+pub trait Module: Send + Sync + Sized + 'static {
+	/// The name of the module.
+	///
+	/// Used as the name of the task and in the usage information.
+	const NAME: &'static str;
+
+	/// The priority of the task. See [`task::priority`](../task/priority/index.html).
+	const PRIORITY: i32 = task::priority::DEFAULT;
+
+	/// The stack size of the task, in bytes.
+	const STACK_SIZE: usize = 8192;
+
+	/// The usage information, printed by [`print_usage`](fn.print_usage.html).
+	///
+	/// Its commands should include `start`, and
+	/// [`Command::DEFAULT_COMMANDS`](../usage/struct.Command.html#associatedconstant.DEFAULT_COMMANDS).
+	/// If `None`, only the `start`, `stop`, `status` and `log` commands are shown.
+	const USAGE: Option<&'static Usage> = None;
+
+	/// Create the module, from the arguments given to the `start` command.
+	///
+	/// The first argument is `start`. On error, the returned status code is
+	/// returned by the `start` command.
+	fn instantiate(args: &[&str]) -> Result<Self, i32>;
+
+	/// The main loop of the module.
+	///
+	/// This runs in a new PX4 task. It should return soon after `should_exit`
+	/// is set by the `stop` command or by PX4 shutting down.
+	fn run(&self, should_exit: &ShouldExit);
+
+	/// Print the status of the module, for the `status` command.
+	fn print_status(&self) {
+		info!("Running");
+	}
+
+	/// Handle any command other than `start`, `stop`, `status` and `log`.
+	///
+	/// The first argument is the command. By default, this prints the usage
+	/// information and returns 1.
+	fn custom_command(args: &[&str]) -> i32 {
+		let _ = args;
+		print_usage::<Self>();
+		1
+	}
+}
+
+struct Instance {
+	type_id: TypeId,
+	module: Arc<dyn Any + Send + Sync>,
+	should_exit: ShouldExit,
+	task: Task,
+}
+
+static INSTANCES: Mutex<Vec<Instance>> = Mutex::new(Vec::new());
+
+fn instances() -> MutexGuard<'static, Vec<Instance>> {
+	INSTANCES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Remove the instance of `M` if it is no longer running, and return whether
+/// it is still running.
+fn clean_up<M: Module>(instances: &mut Vec<Instance>) -> Option<usize> {
+	let i = instances
+		.iter()
+		.position(|i| i.type_id == TypeId::of::<M>())?;
+	if instances[i].task.is_finished() {
+		instances.remove(i);
+		None
+	} else {
+		Some(i)
+	}
+}
+
+/// Check whether the module is running.
+pub fn is_running<M: Module>() -> bool {
+	clean_up::<M>(&mut instances()).is_some()
+}
+
+/// Handle the `start`, `stop`, `status` and `log` commands for module `M`.
+///
+/// `args` are the arguments of the `#[px4_module_main]` function, including
+/// the name of the module as the first argument. Returns the status code.
+pub fn main<M: Module>(args: &[&str]) -> i32 {
+	match args.get(1).copied() {
+		Some("start") => start::<M>(&args[1..]),
+		Some("stop") => stop::<M>(),
+		Some("status") => status::<M>(),
+		Some("log") => crate::log_command(&args[1..]),
+		Some("help") | Some("-h") | Some("--help") | Some("usage") => {
+			print_usage::<M>();
+			0
+		}
+		Some(_) => M::custom_command(&args[1..]),
+		None => {
+			print_usage::<M>();
+			1
+		}
+	}
+}
+
+/// Print the usage information of module `M`.
+pub fn print_usage<M: Module>() {
+	if let Some(usage) = M::USAGE {
+		usage.print();
+		return;
+	}
+	info_raw!("Usage: {} <command> [arguments...]\n", M::NAME);
+	info_raw!(" Commands:\n");
+	info_raw!("\n   start\n");
+	info_raw!("\n   stop\n");
+	info_raw!("\n   status        print status info\n");
+	info_raw!("\n   log           show or set the log filter\n");
+	info_raw!("     [<filter>]  e.g. warn,{}=debug\n", M::NAME);
+}
+
+fn start<M: Module>(args: &[&str]) -> i32 {
+	let mut instances = instances();
+	if clean_up::<M>(&mut instances).is_some() {
+		error!("Already running");
+		return 1;
+	}
+	let module = match M::instantiate(args) {
+		Ok(module) => Arc::new(module),
+		Err(status) => return status,
+	};
+	let should_exit = ShouldExit::new();
+	let task = {
+		let module = module.clone();
+		let should_exit = should_exit.clone();
+		task::spawn(M::NAME, M::PRIORITY, M::STACK_SIZE, move || {
+			module.run(&should_exit)
+		})
+	};
+	match task {
+		Ok(task) => {
+			instances.push(Instance {
+				type_id: TypeId::of::<M>(),
+				module,
+				should_exit,
+				task,
+			});
+			0
+		}
+		Err(e) => {
+			error!("Unable to start task: {}", e);
+			1
+		}
+	}
+}
+
+fn stop<M: Module>() -> i32 {
+	let instance = {
+		let mut instances = instances();
+		match clean_up::<M>(&mut instances) {
+			Some(i) => instances.remove(i),
+			None => {
+				warn!("Not running");
+				return 1;
+			}
+		}
+	};
+	instance.should_exit.set();
+	let start = Instant::now();
+	while !instance.task.is_finished() {
+		if start.elapsed() > STOP_TIMEOUT {
+			error!("Timeout while waiting for the module to stop");
+			instances().push(instance);
+			return 1;
+		}
+		std::thread::sleep(Duration::from_millis(10));
+	}
+	0
+}
+
+fn status<M: Module>() -> i32 {
+	let module = {
+		let mut instances = instances();
+		clean_up::<M>(&mut instances).map(|i| instances[i].module.clone())
+	};
+	match &module {
+		Some(module) => module.downcast_ref::<M>().unwrap().print_status(),
+		None => info!("Not running"),
+	}
+	let panics = crate::panic_count();
+	if panics > 0 {
+		warn!("Panicked {} time(s) since loaded", panics);
+	}
+	if module.is_some() {
+		0
+	} else {
+		1
+	}
+}
@@ -0,0 +1,402 @@
+//! Bindings to the parameter system of PX4.
+//!
+//! PX4 parameters are named values of type `i32` or `f32`, which can be
+//! changed at runtime (e.g. through `param set` or a ground station), and are
+//! stored across reboots.
+//!
+//! These bindings follow the C API of PX4 v1.13 and later. Earlier versions
+//! use other values for the types of parameters (see
+//! [`ParamType`](enum.ParamType.html)).
+//!
+//! A [`Param<T>`](struct.Param.html) is a handle to a parameter, found by
+//! name. Finding it checks that the parameter exists and has type `T`, after
+//! which getting and setting the value can't fail because of the name or
+//! type anymore.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! use px4::param::Param;
+//!
+//! let rate = Param::<i32>::find("BLINK_RATE")?;
+//! let gain = Param::<f32>::find("BLINK_GAIN")?;
+//! info!("Blinking at {} Hz with gain {}", rate.get(), gain.get());
+//! gain.set(1.5)?;
+//! ```
+//!
+//! A `Param` handle doesn't cache the value: every call to
+//! [`get`](struct.Param.html#method.get) reads it from PX4.
+//!
+//! ## Reacting to changes
+//!
+//! A [`ParamSet`](struct.ParamSet.html) keeps the values of a group of
+//! parameters, and reads them again when PX4 publishes `parameter_update`,
+//! so a running module picks up new values without a restart.
+//!
+//! ## Defining parameters
+//!
+//! Like for modules written in C++, the parameters of a module must be
+//! defined in the firmware, using a `module.yaml` file. Parameters can be
+//! declared as a struct with `#[px4_parameters]`, which turns the fields into
+//! `Param` handles, and generates the metadata:
+//!
+//! ```
+//! use px4::px4_parameters;
+//!
+//! #[px4_parameters(prefix = "BLINK_", group = "Blink")]
+//! struct BlinkParams {
+//!   /// Blink rate.
+//!   ///
+//!   /// The number of times per second the LED blinks.
+//!   #[param(default = 2, min = 1, max = 10, unit = "Hz")]
+//!   rate: i32,
+//!
+//!   /// Brightness.
+//!   #[param(name = "BLINK_BRIGHT", default = 0.5, min = 0, max = 1, decimal = 2)]
+//!   brightness: f32,
+//! }
+//!
+//! # px4::mock::param::define_defaults(BlinkParams::DEFINITIONS);
+//! let params = BlinkParams::find().unwrap();
+//! let rate: i32 = params.rate.get();
+//! ```
+//!
+//! Each field needs a doc comment, of which the first paragraph is the short
+//! description, and the rest the long description. The `#[param(...)]`
+//! attribute can set the `name` (otherwise the prefix followed by the field
+//! name in upper case), `default` (otherwise 0), `min`, `max`, `unit`,
+//! `decimal` and `reboot_required`. Negative numbers are given as strings,
+//! e.g. `min = "-1"`.
+//!
+//! The struct gets:
+//!
+//!  - `find()`, which finds all parameters, and fails if any of them is
+//!    missing or of the wrong type.
+//!  - `MODULE_YAML`, the `parameters` section of a `module.yaml` file for the
+//!    PX4 build, with `module_name` set to `module = "..."` or the crate name.
+//!  - `DEFINITIONS`, the same metadata as a list of
+//!    [`Definition`](struct.Definition.html)s, which can be given to
+//!    [`mock::param::define_defaults`](../mock/param/fn.define_defaults.html).
+//!  - An implementation of [`ParamGroup`](trait.ParamGroup.html), with a
+//!    struct containing all values, named after the struct with `Values`
+//!    appended (e.g. `BlinkParamsValues`).
+//!
+//! The PX4 build doesn't run Rust code, so this metadata isn't picked up by
+//! itself: write `MODULE_YAML` to a `module.yaml` file, and pass that file
+//! as `MODULE_CONFIG` to `px4_add_rust_module` (or `px4_add_module`). To keep
+//! it up to date, write it from a build script or a test, e.g.:
+//!
+//! ```ignore
+//! #[test]
+//! fn module_yaml() {
+//!   let path = concat!(env!("CARGO_MANIFEST_DIR"), "/module.yaml");
+//!   if std::fs::read_to_string(path).ok().as_deref() != Some(BlinkParams::MODULE_YAML) {
+//!     std::fs::write(path, BlinkParams::MODULE_YAML).unwrap();
+//!     panic!("module.yaml was out of date, and has been updated");
+//!   }
+//! }
+//! ```
+
+mod set;
+
+pub use self::set::{ParamGroup, ParamSet};
+
+use crate::px4_message;
+use std::error::Error;
+use std::ffi::{c_void, CString};
+use std::fmt;
+use std::marker::PhantomData;
+
+#[cfg(feature = "mock")]
+use crate::mock::param::{param_find, param_get, param_notify_changes, param_set, param_type};
+
+#[cfg(not(feature = "mock"))]
+extern "C" {
+	fn param_find(name: *const std::os::raw::c_char) -> u16;
+	fn param_type(param: u16) -> u8;
+	fn param_get(param: u16, val: *mut c_void) -> i32;
+	fn param_set(param: u16, val: *const c_void) -> i32;
+	fn param_notify_changes();
+}
+
+/// The message published by PX4 when parameters change.
+///
+/// Has the fields of PX4 v1.12 and later. Older versions of PX4 only fill
+/// in `timestamp` and `instance`. See [`ParamSet`](struct.ParamSet.html) to react to changes.
+#[px4_message("msg/parameter_update.msg")]
+pub struct parameter_update;
+
+impl parameter_update {
+	#[cfg(feature = "mock")]
+	pub(crate) fn new(instance: u32) -> Self {
+		parameter_update {
+			timestamp: 0,
+			instance,
+			get_count: 0,
+			set_count: 0,
+			find_count: 0,
+			export_count: 0,
+			active: 0,
+			changed: 0,
+			custom_default: 0,
+		}
+	}
+
+	/// A counter incremented on every change.
+	pub fn instance(&self) -> u32 {
+		self.instance
+	}
+}
+
+/// The handle returned by `param_find` for unknown parameters.
+///
+/// Equivalent to `PARAM_INVALID` in C and C++.
+pub const PARAM_INVALID: u16 = 0xffff;
+
+/// The type of a parameter, as returned by `param_type`.
+///
+/// The equivalent of `param_type_t` in C and C++, which is a `uint8_t` since
+/// PX4 v1.13, with `PARAM_TYPE_INT32 = 1` and `PARAM_TYPE_FLOAT = 2`. Older
+/// versions of PX4 used different values, and are not supported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParamType {
+	Int32,
+	Float,
+	/// Any other type, with the raw `param_type_t` value. `PARAM_TYPE_UNKNOWN`
+	/// is 0.
+	Other(u8),
+}
+
+impl ParamType {
+	const INT32: u8 = 1;
+	const FLOAT: u8 = 2;
+
+	/// From the raw `param_type_t` value.
+	pub fn from_raw(raw: u8) -> ParamType {
+		match raw {
+			ParamType::INT32 => ParamType::Int32,
+			ParamType::FLOAT => ParamType::Float,
+			_ => ParamType::Other(raw),
+		}
+	}
+
+	/// The raw `param_type_t` value.
+	pub fn to_raw(self) -> u8 {
+		match self {
+			ParamType::Int32 => ParamType::INT32,
+			ParamType::Float => ParamType::FLOAT,
+			ParamType::Other(raw) => raw,
+		}
+	}
+}
+
+impl fmt::Display for ParamType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ParamType::Int32 => write!(f, "int32"),
+			ParamType::Float => write!(f, "float"),
+			ParamType::Other(raw) => write!(f, "type {}", raw),
+		}
+	}
+}
+
+/// A type which parameters can have: `i32` or `f32`.
+pub trait ParamValue: Copy + Default + sealed::Sealed {
+	/// The type of parameters with values of this type.
+	const TYPE: ParamType;
+}
+
+impl ParamValue for i32 {
+	const TYPE: ParamType = ParamType::Int32;
+}
+
+impl ParamValue for f32 {
+	const TYPE: ParamType = ParamType::Float;
+}
+
+mod sealed {
+	pub trait Sealed {}
+	impl Sealed for i32 {}
+	impl Sealed for f32 {}
+}
+
+/// The value of a parameter of any type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+	Int32(i32),
+	Float(f32),
+}
+
+impl Value {
+	/// The type of the value.
+	pub fn param_type(self) -> ParamType {
+		match self {
+			Value::Int32(_) => ParamType::Int32,
+			Value::Float(_) => ParamType::Float,
+		}
+	}
+}
+
+impl From<i32> for Value {
+	fn from(v: i32) -> Value {
+		Value::Int32(v)
+	}
+}
+
+impl From<f32> for Value {
+	fn from(v: f32) -> Value {
+		Value::Float(v)
+	}
+}
+
+/// The definition of a parameter, with the metadata shown by ground stations.
+///
+/// Generated by `#[px4_parameters]`. See the [module documentation](index.html).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Definition {
+	pub name: &'static str,
+	/// A short description, on one line.
+	pub short: &'static str,
+	/// A longer description. May be empty.
+	pub long: &'static str,
+	pub default: Value,
+	pub min: Option<Value>,
+	pub max: Option<Value>,
+	pub unit: Option<&'static str>,
+	/// The number of decimal places to show.
+	pub decimal: Option<u32>,
+	/// Whether the system needs a reboot for a change to take effect.
+	pub reboot_required: bool,
+}
+
+/// A handle to a parameter of type `T`.
+///
+/// See the [module documentation](index.html).
+#[derive(Clone, Debug)]
+pub struct Param<T: ParamValue> {
+	handle: u16,
+	name: String,
+	phantom: PhantomData<T>,
+}
+
+impl<T: ParamValue> Param<T> {
+	/// Find the parameter with the given name.
+	///
+	/// Fails if it doesn't exist or if it is not of type `T`.
+	pub fn find(name: &str) -> Result<Self, ParamError> {
+		let c_name = CString::new(name).map_err(|_| ParamError::NotFound {
+			name: name.to_string(),
+		})?;
+		let handle = unsafe { param_find(c_name.as_ptr()) };
+		if handle == PARAM_INVALID {
+			return Err(ParamError::NotFound {
+				name: name.to_string(),
+			});
+		}
+		let actual = ParamType::from_raw(unsafe { param_type(handle) });
+		if actual != T::TYPE {
+			return Err(ParamError::WrongType {
+				name: name.to_string(),
+				expected: T::TYPE,
+				actual,
+			});
+		}
+		Ok(Param {
+			handle,
+			name: name.to_string(),
+			phantom: PhantomData,
+		})
+	}
+
+	/// The name of the parameter.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// The handle used by PX4 for this parameter, i.e. its `param_t`.
+	pub fn handle(&self) -> u16 {
+		self.handle
+	}
+
+	/// Get the current value.
+	pub fn get(&self) -> T {
+		let mut value = T::default();
+		let r = unsafe { param_get(self.handle, &mut value as *mut T as *mut c_void) };
+		assert!(r == 0, "param_get failed for {}", self.name);
+		value
+	}
+
+	/// Set a new value.
+	///
+	/// PX4 notifies all modules of the change by publishing a
+	/// `parameter_update` message.
+	pub fn set(&self, value: T) -> Result<(), ParamError> {
+		let r = unsafe { param_set(self.handle, &value as *const T as *const c_void) };
+		if r == 0 {
+			Ok(())
+		} else {
+			Err(ParamError::Set {
+				name: self.name.clone(),
+				code: r,
+			})
+		}
+	}
+}
+
+/// Notify all modules that parameters have changed, by publishing a
+/// `parameter_update` message.
+///
+/// The equivalent of `param_notify_changes()` in C and C++.
+pub fn notify_changes() {
+	unsafe { param_notify_changes() }
+}
+
+/// An error from finding or setting a parameter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParamError {
+	/// No parameter with this name exists.
+	NotFound { name: String },
+	/// The parameter exists, but has a different type.
+	WrongType {
+		name: String,
+		expected: ParamType,
+		actual: ParamType,
+	},
+	/// `param_set` returned an error.
+	Set { name: String, code: i32 },
+	/// Subscribing to `parameter_update` failed, with the given error code.
+	Subscribe(i32),
+}
+
+impl ParamError {
+	/// The name of the parameter, if the error is about a single parameter.
+	pub fn name(&self) -> Option<&str> {
+		match self {
+			ParamError::NotFound { name } => Some(name),
+			ParamError::WrongType { name, .. } => Some(name),
+			ParamError::Set { name, .. } => Some(name),
+			ParamError::Subscribe(_) => None,
+		}
+	}
+}
+
+impl fmt::Display for ParamError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ParamError::NotFound { name } => write!(f, "parameter `{}` not found", name),
+			ParamError::WrongType {
+				name,
+				expected,
+				actual,
+			} => write!(f, "parameter `{}` is of type {}, not {}", name, actual, expected),
+			ParamError::Set { name, code } => {
+				write!(f, "unable to set parameter `{}`: error {}", name, code)
+			}
+			ParamError::Subscribe(code) => {
+				write!(f, "unable to subscribe to `parameter_update`: error {}", code)
+			}
+		}
+	}
+}
+
+impl Error for ParamError {}
@@ -0,0 +1,146 @@
+use super::{parameter_update, ParamError};
+use crate::uorb::{Subscribe, Subscription};
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+
+/// Room for the `parameter_update` message as PX4 advertises it.
+///
+/// `orb_copy` copies the size the topic was advertised with, which is larger
+/// than our definition if PX4 has more fields than we know about.
+#[repr(C)]
+struct UpdateBuffer {
+	message: MaybeUninit<parameter_update>,
+	_spare: [MaybeUninit<u8>; 256],
+}
+
+/// Mark the latest `parameter_update` as seen.
+fn consume(subscription: &Subscription<parameter_update>) {
+	let mut buffer = UpdateBuffer {
+		message: MaybeUninit::uninit(),
+		_spare: [MaybeUninit::uninit(); 256],
+	};
+	// The message itself is not used, so it doesn't matter whether PX4 filled
+	// in all of it.
+	let _ = unsafe { subscription.get_into_ptr(buffer.message.as_mut_ptr()) };
+}
+
+/// A group of parameters, which can be read all at once.
+///
+/// Implemented by `#[px4_parameters]`, with a generated `Values` struct
+/// named after the struct with the handles, e.g. `BlinkParamsValues`.
+pub trait ParamGroup: Sized {
+	/// The values of all parameters in the group.
+	type Values;
+
+	/// Find all parameters in the group.
+	fn find() -> Result<Self, ParamError>;
+
+	/// Read the current values of all parameters.
+	fn values(&self) -> Self::Values;
+}
+
+/// The values of a [`ParamGroup`](trait.ParamGroup.html), which are read
+/// again whenever parameters change.
+///
+/// Dereferences to the values as they were last read. Call
+/// [`updated`](#method.updated) regularly, such as once per iteration of
+/// the main loop of a controller, to pick up changes made while the module
+/// is running.
+///
+/// ```
+/// use px4::param::ParamSet;
+/// use px4::px4_parameters;
+///
+/// #[px4_parameters(prefix = "CTL_")]
+/// struct ControlParams {
+///   /// Proportional gain.
+///   #[param(default = 0.8)]
+///   p: f32,
+/// }
+///
+/// # px4::mock::param::define_defaults(ControlParams::DEFINITIONS);
+/// let mut params = ParamSet::<ControlParams>::new().unwrap();
+/// params.on_change(|values| log::info!("new gain: {}", values.p));
+/// loop {
+///   params.updated();
+///   let output = params.p * 1.5;
+///   # break;
+/// }
+/// ```
+///
+/// Changes are detected through the `parameter_update` message, which PX4
+/// publishes whenever a parameter is changed.
+pub struct ParamSet<G: ParamGroup> {
+	group: G,
+	values: G::Values,
+	subscription: Subscription<parameter_update>,
+	callbacks: Vec<Callback<G>>,
+}
+
+type Callback<G> = Box<dyn FnMut(&<G as ParamGroup>::Values) + Send>;
+
+impl<G: ParamGroup> ParamSet<G> {
+	/// Find the parameters and read their values.
+	pub fn new() -> Result<Self, ParamError> {
+		let subscription = parameter_update::subscribe().map_err(ParamError::Subscribe)?;
+		// Earlier changes are already included in the values read below.
+		if let Ok(true) = subscription.check() {
+			consume(&subscription);
+		}
+		let group = G::find()?;
+		let values = group.values();
+		Ok(ParamSet {
+			group,
+			values,
+			subscription,
+			callbacks: Vec::new(),
+		})
+	}
+
+	/// Check whether any parameter changed, and if so, read the values again
+	/// and call the callbacks registered with [`on_change`](#method.on_change).
+	///
+	/// Returns true if the values were read again.
+	pub fn updated(&mut self) -> bool {
+		match self.subscription.check() {
+			Ok(true) => {
+				consume(&self.subscription);
+				self.reload();
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Read the values again, and call the callbacks, whether or not any
+	/// parameter changed.
+	pub fn reload(&mut self) {
+		self.values = self.group.values();
+		for callback in &mut self.callbacks {
+			callback(&self.values);
+		}
+	}
+
+	/// Register a function to call with the new values whenever they are read
+	/// again.
+	pub fn on_change(&mut self, callback: impl FnMut(&G::Values) + Send + 'static) {
+		self.callbacks.push(Box::new(callback));
+	}
+
+	/// The values as they were last read.
+	pub fn values(&self) -> &G::Values {
+		&self.values
+	}
+
+	/// The parameter handles.
+	pub fn group(&self) -> &G {
+		&self.group
+	}
+}
+
+impl<G: ParamGroup> Deref for ParamSet<G> {
+	type Target = G::Values;
+	fn deref(&self) -> &G::Values {
+		&self.values
+	}
+}
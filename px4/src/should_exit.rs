@@ -0,0 +1,114 @@
+use crate::hrt::{self, HrtInstant};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once, Weak};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "mock")]
+use crate::mock::shutdown::px4_register_shutdown_hook;
+
+#[cfg(not(feature = "mock"))]
+extern "C" {
+	fn px4_register_shutdown_hook(hook: extern "C" fn() -> bool) -> i32;
+}
+
+/// How often [`ShouldExit::sleep`](struct.ShouldExit.html#method.sleep)
+/// checks whether to stop sleeping.
+const SLEEP_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long the shutdown hook holds up the shutdown of PX4, waiting for tasks
+/// to exit.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A request for a task to exit.
+///
+/// The [`module` framework](module/index.html) hands one of these to the
+/// main loop of a module, and sets it when the `stop` command is given.
+///
+/// All `ShouldExit` tokens are also set when PX4 shuts down, which then
+/// waits (for a few seconds at most) for all tasks started with
+/// [`task::spawn`](task/fn.spawn.html) to exit.
+///
+/// Cloning a `ShouldExit` gives another handle to the same token.
+///
+/// ## Example
+///
+/// ```ignore
+/// while !should_exit.is_set() {
+///   if sub.wait_or_exit(Duration::from_millis(100), &should_exit)? {
+///     let value = sub.get()?;
+///     // ...
+///   }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShouldExit {
+	flag: Arc<AtomicBool>,
+}
+
+static TOKENS: Mutex<Vec<Weak<AtomicBool>>> = Mutex::new(Vec::new());
+
+/// Called by PX4 repeatedly while shutting down, until it returns true.
+///
+/// Sets all tokens, and then reports to be ready once all tasks started by
+/// [`task::spawn`](task/fn.spawn.html) have exited, or once
+/// `SHUTDOWN_TIMEOUT` has passed since the first call.
+extern "C" fn shutdown_hook() -> bool {
+	static FIRST_CALL: Mutex<Option<Instant>> = Mutex::new(None);
+	let mut first_call = FIRST_CALL.lock().unwrap_or_else(|e| e.into_inner());
+	let start = *first_call.get_or_insert_with(Instant::now);
+	let tokens = TOKENS.lock().unwrap_or_else(|e| e.into_inner());
+	for token in tokens.iter().filter_map(Weak::upgrade) {
+		token.store(true, Ordering::Relaxed);
+	}
+	let ready = crate::task::all_finished() || start.elapsed() >= SHUTDOWN_TIMEOUT;
+	if ready {
+		*first_call = None;
+	}
+	ready
+}
+
+impl ShouldExit {
+	/// Create a new token, which is not set.
+	pub fn new() -> Self {
+		static REGISTER: Once = Once::new();
+		REGISTER.call_once(|| unsafe {
+			px4_register_shutdown_hook(shutdown_hook);
+		});
+		let flag = Arc::new(AtomicBool::new(false));
+		let mut tokens = TOKENS.lock().unwrap_or_else(|e| e.into_inner());
+		tokens.retain(|t| t.strong_count() > 0);
+		tokens.push(Arc::downgrade(&flag));
+		ShouldExit { flag }
+	}
+
+	/// Check whether the task should exit.
+	pub fn is_set(&self) -> bool {
+		self.flag.load(Ordering::Relaxed)
+	}
+
+	/// Request the task to exit.
+	pub fn set(&self) {
+		self.flag.store(true, Ordering::Relaxed);
+	}
+
+	/// Sleep for the given duration, unless exiting is requested earlier.
+	///
+	/// Returns [`is_set()`](struct.ShouldExit.html#method.is_set).
+	pub fn sleep(&self, duration: Duration) -> bool {
+		let end = HrtInstant::now() + duration;
+		while !self.is_set() {
+			let now = HrtInstant::now();
+			if now >= end {
+				break;
+			}
+			hrt::sleep((end - now).min(SLEEP_INTERVAL));
+		}
+		self.is_set()
+	}
+}
+
+impl Default for ShouldExit {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -0,0 +1,175 @@
+//! Spawning PX4 tasks.
+//!
+//! Threads started with `std::thread` are invisible to PX4: they run with
+//! the default scheduling priority, and don't show up in `top` by name.
+//! [`spawn`](fn.spawn.html) starts a task through `px4_task_spawn_cmd`
+//! instead, like C and C++ modules do.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! use px4::task::{self, priority};
+//!
+//! task::spawn("my_controller", priority::DEFAULT, 4096, move || {
+//!   // ...
+//! }).unwrap();
+//! ```
+
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "mock")]
+use crate::mock::task::px4_task_spawn_cmd;
+
+#[cfg(not(feature = "mock"))]
+extern "C" {
+	fn px4_task_spawn_cmd(
+		name: *const c_char,
+		scheduler: i32,
+		priority: i32,
+		stack_size: i32,
+		entry: unsafe extern "C" fn(i32, *mut *mut c_char) -> i32,
+		argv: *const *const c_char,
+	) -> i32;
+}
+
+/// The scheduling policy used for tasks: `SCHED_FIFO`.
+///
+/// Equivalent to `SCHED_DEFAULT` in C and C++.
+pub const SCHED_DEFAULT: i32 = 1;
+
+/// Task priorities.
+///
+/// Equivalent to the `SCHED_PRIORITY_*` constants in C and C++.
+pub mod priority {
+	#[cfg(not(target_os = "nuttx"))]
+	pub const MAX: i32 = 99;
+	#[cfg(not(target_os = "nuttx"))]
+	pub const MIN: i32 = 1;
+	#[cfg(not(target_os = "nuttx"))]
+	pub const DEFAULT: i32 = 50;
+
+	#[cfg(target_os = "nuttx")]
+	pub const MAX: i32 = 255;
+	#[cfg(target_os = "nuttx")]
+	pub const MIN: i32 = 1;
+	#[cfg(target_os = "nuttx")]
+	pub const DEFAULT: i32 = 100;
+
+	pub const FAST_DRIVER: i32 = MAX;
+	pub const WATCHDOG: i32 = MAX - 5;
+	pub const ACTUATOR_OUTPUTS: i32 = MAX - 15;
+	pub const ATTITUDE_CONTROL: i32 = MAX - 25;
+	pub const SLOW_DRIVER: i32 = MAX - 35;
+	pub const POSITION_CONTROL: i32 = MAX - 40;
+}
+
+/// A handle to a task started by [`spawn`](fn.spawn.html).
+///
+/// PX4 tasks can not be joined, but the handle can be used to check whether
+/// the task finished.
+#[derive(Clone, Debug)]
+pub struct Task {
+	id: i32,
+	finished: Arc<AtomicBool>,
+}
+
+impl Task {
+	/// The PX4 task id.
+	pub fn id(&self) -> i32 {
+		self.id
+	}
+
+	/// Check whether the task finished, either by returning or by panicking.
+	pub fn is_finished(&self) -> bool {
+		self.finished.load(Ordering::Acquire)
+	}
+}
+
+/// Sets the flag when dropped, also when unwinding.
+struct SetOnDrop(Arc<AtomicBool>);
+
+impl Drop for SetOnDrop {
+	fn drop(&mut self) {
+		self.0.store(true, Ordering::Release);
+	}
+}
+
+type Entry = Box<dyn FnOnce() + Send>;
+
+/// The `finished` flags of all spawned tasks which didn't finish yet.
+static RUNNING: Mutex<Vec<Arc<AtomicBool>>> = Mutex::new(Vec::new());
+
+/// Check whether all tasks started by [`spawn`](fn.spawn.html) finished.
+pub(crate) fn all_finished() -> bool {
+	let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+	running.retain(|f| !f.load(Ordering::Acquire));
+	running.is_empty()
+}
+
+/// Spawn a new PX4 task, running the given closure.
+///
+/// `priority` is one of the values in [`priority`](priority/index.html).
+/// `stack_size` is in bytes. Note that Rust code tends to need more stack
+/// space than the equivalent C code.
+///
+/// A panic in the task is caught and logged, just like a panic in the main
+/// thread of the module, and does not take down PX4.
+///
+/// On NuttX, every task has its own file descriptors. Subscriptions must
+/// then be made from within the task itself, instead of moved into it.
+pub fn spawn<F>(name: &str, priority: i32, stack_size: usize, f: F) -> io::Result<Task>
+where
+	F: FnOnce() + Send + 'static,
+{
+	let name = CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+	let finished = Arc::new(AtomicBool::new(false));
+	let guard = SetOnDrop(finished.clone());
+	// The task logs with the tag of the command that spawned it.
+	let tag = crate::logging::tag::current();
+	let entry: Entry = Box::new(move || {
+		let _guard = guard;
+		let _tag = crate::logging::tag::scope(tag);
+		f()
+	});
+	let entry = Box::into_raw(Box::new(entry));
+
+	// The closure is passed to the task as an argument, which PX4 copies.
+	let arg = CString::new(format!("{:x}", entry as usize)).unwrap();
+	let argv = [arg.as_ptr(), std::ptr::null()];
+
+	let id = unsafe {
+		px4_task_spawn_cmd(
+			name.as_ptr(),
+			SCHED_DEFAULT,
+			priority,
+			stack_size as i32,
+			run_task,
+			argv.as_ptr(),
+		)
+	};
+
+	if id < 0 {
+		let error = io::Error::last_os_error();
+		drop(unsafe { Box::from_raw(entry) });
+		return Err(error);
+	}
+
+	RUNNING.lock().unwrap_or_else(|e| e.into_inner()).push(finished.clone());
+	Ok(Task { id, finished })
+}
+
+unsafe extern "C" fn run_task(argc: i32, argv: *mut *mut c_char) -> i32 {
+	// The argument we passed is the last one. (The first is the task name.)
+	let arg = CStr::from_ptr(*argv.offset(argc as isize - 1));
+	let entry = usize::from_str_radix(arg.to_str().unwrap(), 16).unwrap() as *mut Entry;
+	let entry = Box::from_raw(entry);
+	match catch_unwind(AssertUnwindSafe(entry)) {
+		Ok(()) => 0,
+		Err(_) => -1,
+	}
+}
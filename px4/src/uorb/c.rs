@@ -12,6 +12,7 @@ pub mod priority {
 	pub const MAX: i32 = 255;
 }
 
+#[cfg(not(feature = "mock"))]
 extern "C" {
 	pub fn orb_advertise_multi_queue(meta: *const Metadata, data: *const u8, instance: *mut i32, priority: i32, queue_size: u32) -> usize;
 	pub fn orb_unadvertise(handle: usize) -> i32;
@@ -27,6 +28,70 @@ extern "C" {
 	pub fn orb_priority(handle: i32, priority: *mut i32) -> i32;
 	pub fn orb_set_interval(handle: i32, interval: u32) -> i32;
 	pub fn orb_get_interval(handle: i32, interval: *mut u32) -> i32;
+	#[cfg(not(target_os = "nuttx"))]
+	pub fn px4_poll(fds: *mut PollFd, nfds: u32, timeout: i32) -> i32;
+	#[cfg(target_os = "nuttx")]
+	#[link_name = "poll"]
+	pub fn px4_poll(fds: *mut PollFd, nfds: u32, timeout: i32) -> i32;
+}
+
+#[cfg(feature = "mock")]
+pub use crate::mock::uorb::{
+	errno, orb_advertise_multi_queue, orb_check, orb_copy, orb_exists, orb_get_interval,
+	orb_group_count, orb_priority, orb_publish, orb_set_interval, orb_stat, orb_subscribe,
+	orb_subscribe_multi, orb_unadvertise, orb_unsubscribe, px4_poll, set_errno,
+};
+
+/// The `errno` value of the last failed call on this thread.
+#[cfg(not(feature = "mock"))]
+pub fn errno() -> i32 {
+	std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
+
+#[cfg(not(any(feature = "mock", target_os = "linux", target_os = "nuttx", target_os = "macos")))]
+compile_error!("PX4 runs on Linux, NuttX and macOS; use the `mock` feature on other targets");
+
+/// Set the `errno` value of this thread.
+///
+/// Used to clear it before calls which don't always set it on failure.
+#[cfg(not(feature = "mock"))]
+pub fn set_errno(errno: i32) {
+	extern "C" {
+		#[cfg_attr(target_os = "linux", link_name = "__errno_location")]
+		#[cfg_attr(target_os = "nuttx", link_name = "__errno")]
+		#[cfg_attr(target_os = "macos", link_name = "__error")]
+		fn errno_location() -> *mut i32;
+	}
+	unsafe { *errno_location() = errno };
+}
+
+/// The `events` of a `PollFd` to wait for new data.
+const POLLIN: u32 = 0x01;
+
+/// Equivalent to `px4_pollfd_struct_t` in C and C++, which is `struct pollfd`
+/// on NuttX.
+///
+/// The types of `events` and `revents` differ between platforms and versions
+/// of NuttX, so only the start of the structure is spelled out: `fd`, and
+/// `events` such that `POLLIN` ends up in the first byte(s). The rest is
+/// left zeroed, and `revents` is not read: with a single file descriptor, the
+/// return value of `px4_poll` says enough.
+#[repr(C)]
+pub struct PollFd {
+	pub fd: i32,
+	pub events: u32,
+	_rest: [*mut std::ffi::c_void; 4],
+}
+
+impl PollFd {
+	/// Wait for new data on the given subscription handle.
+	pub fn new(fd: i32) -> Self {
+		PollFd {
+			fd,
+			events: POLLIN,
+			_rest: [std::ptr::null_mut(); 4],
+		}
+	}
 }
 
 /// The meta data of a message.
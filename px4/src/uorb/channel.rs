@@ -0,0 +1,198 @@
+use super::{Message, Publisher, Subscription};
+use crate::hrt::{self, HrtInstant};
+use log::error;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How often a pump waiting on a channel checks whether it should stop.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A background thread forwarding messages between uORB and a channel.
+///
+/// Created by
+/// [`Subscription::into_receiver`](struct.Subscription.html#method.into_receiver) or
+/// [`Publisher::into_sender`](struct.Publisher.html#method.into_sender).
+///
+/// The thread stops when the other end of the channel is dropped, or when
+/// [`stop`](struct.Pump.html#method.stop) is called.
+/// [`Drop`](struct.Pump.html#impl-Drop)ping the `Pump` also stops the
+/// thread, and waits for it to finish.
+pub struct Pump<H> {
+	stop: Arc<AtomicBool>,
+	thread: Option<JoinHandle<SameTask<H>>>,
+	// Only `Send` if `H` is, since `stop` gives back the `H`.
+	phantom: PhantomData<H>,
+}
+
+/// A subscription or publisher moved to the pump thread, and back.
+///
+/// uORB handles may only be valid in the task that created them, but the
+/// threads of a task share them.
+struct SameTask<H>(H);
+
+// SAFETY: A `SameTask` only exists inside `Pump`. It is moved to the pump
+// thread, which belongs to the task that called `Pump::spawn`, and back to
+// the owner of the `Pump`, which can only be in another task if `H: Send`.
+unsafe impl<H> Send for SameTask<H> {}
+
+impl<H> Pump<H> {
+	fn spawn<F>(name: String, handle: H, f: F) -> Self
+	where
+		F: FnOnce(&mut H, &AtomicBool) + Send + 'static,
+		H: 'static,
+	{
+		let stop = Arc::new(AtomicBool::new(false));
+		let thread_stop = stop.clone();
+		let handle = SameTask(handle);
+		let tag = crate::logging::tag::current();
+		let thread = thread::Builder::new()
+			.name(name)
+			.spawn(move || {
+				let _tag = crate::logging::tag::scope(tag);
+				let mut handle = handle;
+				f(&mut handle.0, &thread_stop);
+				handle
+			})
+			.expect("unable to spawn uORB pump thread");
+		Pump {
+			stop,
+			thread: Some(thread),
+			phantom: PhantomData,
+		}
+	}
+
+	/// Check whether the thread is still running.
+	pub fn is_running(&self) -> bool {
+		self.thread.as_ref().is_some_and(|t| !t.is_finished())
+	}
+
+	/// Stop the thread, and get the subscription or publisher back.
+	///
+	/// If the thread panicked, the panic is propagated.
+	pub fn stop(mut self) -> H {
+		self.stop.store(true, Ordering::Relaxed);
+		let thread = self.thread.take().unwrap();
+		thread.join().unwrap_or_else(|e| std::panic::resume_unwind(e)).0
+	}
+}
+
+impl<H> Drop for Pump<H> {
+	fn drop(&mut self) {
+		if let Some(thread) = self.thread.take() {
+			self.stop.store(true, Ordering::Relaxed);
+			let _ = thread.join();
+		}
+	}
+}
+
+/// Sleep until `end`, in steps of at most `STOP_CHECK_INTERVAL`.
+///
+/// Returns false, possibly early, if the pump should stop.
+fn sleep_until(end: HrtInstant, stop: &AtomicBool) -> bool {
+	loop {
+		if stop.load(Ordering::Relaxed) {
+			return false;
+		}
+		let now = HrtInstant::now();
+		if now >= end {
+			return true;
+		}
+		hrt::sleep((end - now).min(STOP_CHECK_INTERVAL));
+	}
+}
+
+impl<T: Message + Send + 'static> Subscription<T> {
+	/// Move the subscription to a new thread which forwards all updates to a
+	/// channel.
+	///
+	/// Updates are forwarded as soon as they arrive, but at most one per
+	/// `interval`, which limits the rate at which messages arrive on the
+	/// channel. When updates arrive faster than that, some are not forwarded.
+	///
+	/// ## Example
+	///
+	/// ```ignore
+	/// let (rx, pump) = foo::subscribe()?.into_receiver(Duration::from_millis(100));
+	/// for foo in rx.iter().take(10) {
+	///   info!("Received: {:?}", foo);
+	/// }
+	/// let sub = pump.stop();
+	/// ```
+	pub fn into_receiver(self, interval: Duration) -> (Receiver<T>, Pump<Subscription<T>>) {
+		let (tx, rx) = mpsc::channel();
+		let name = format!("uorb rx {}", T::metadata().name());
+		let pump = Pump::spawn(name, self, move |sub, stop| {
+			let mut next = HrtInstant::now();
+			while sleep_until(next, stop) {
+				match sub.wait(STOP_CHECK_INTERVAL) {
+					Ok(false) => continue,
+					Ok(true) => {}
+					Err(e) => {
+						error!("Unable to check `{}`: {}", T::metadata().name(), e);
+						break;
+					}
+				}
+				match sub.get() {
+					Ok(value) => {
+						if tx.send(value).is_err() {
+							break;
+						}
+					}
+					Err(e) => {
+						error!("Unable to copy `{}`: {}", T::metadata().name(), e);
+						break;
+					}
+				}
+				next = HrtInstant::now() + interval;
+			}
+		});
+		(rx, pump)
+	}
+}
+
+impl<T: Message + Send + 'static> Publisher<T> {
+	/// Move the publisher to a new thread which publishes everything sent
+	/// into a channel.
+	///
+	/// At most one message is published per `min_interval`. When messages
+	/// arrive faster than that, only the latest one is published.
+	///
+	/// ## Example
+	///
+	/// ```ignore
+	/// let (tx, pump) = foo::advertise().into_sender(Duration::from_millis(10));
+	/// std::thread::spawn(move || {
+	///   tx.send(foo { timestamp: 0, a: 1, b: 2 }).unwrap();
+	/// });
+	/// ```
+	pub fn into_sender(self, min_interval: Duration) -> (Sender<T>, Pump<Publisher<T>>) {
+		let (tx, rx) = mpsc::channel::<T>();
+		let name = format!("uorb tx {}", T::metadata().name());
+		let pump = Pump::spawn(name, self, move |publisher, stop| {
+			let mut next = HrtInstant::now();
+			while !stop.load(Ordering::Relaxed) {
+				let mut value = match rx.recv_timeout(STOP_CHECK_INTERVAL) {
+					Ok(value) => value,
+					Err(RecvTimeoutError::Timeout) => continue,
+					Err(RecvTimeoutError::Disconnected) => break,
+				};
+				if !sleep_until(next, stop) {
+					break;
+				}
+				while let Ok(newer) = rx.try_recv() {
+					value = newer;
+				}
+				if let Err(e) = publisher.publish(&value) {
+					error!("{}", e);
+					break;
+				}
+				next = HrtInstant::now() + min_interval;
+			}
+		});
+		(tx, pump)
+	}
+}
@@ -0,0 +1,114 @@
+use super::{Message, Subscription};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A thread-safe cache of the latest value of a message.
+///
+/// Since a [`Subscription`](struct.Subscription.html) can not be shared
+/// between threads, this type can be used instead: One thread owns the
+/// subscription and calls [`update`](struct.LatestValue.html#method.update),
+/// while any number of clones of the `LatestValue` can be used from other
+/// threads to read the value.
+///
+/// Cloning a `LatestValue` gives another handle to the same cache.
+///
+/// ## Example
+///
+/// ```ignore
+/// let latest = LatestValue::new();
+/// let reader = latest.clone();
+/// std::thread::spawn(move || loop {
+///   if let Some(value) = reader.get() {
+///     info!("Latest foo: {:?}", value);
+///   }
+///   px4::hrt::sleep(Duration::from_secs(1));
+/// });
+///
+/// let sub = foo::subscribe().unwrap();
+/// loop {
+///   latest.update(&sub).unwrap();
+///   px4::hrt::sleep(Duration::from_millis(10));
+/// }
+/// ```
+pub struct LatestValue<T> {
+	inner: Arc<Mutex<Latest<T>>>,
+}
+
+struct Latest<T> {
+	value: Option<T>,
+	generation: u64,
+}
+
+impl<T> LatestValue<T> {
+	/// Create an empty cache.
+	pub fn new() -> Self {
+		LatestValue {
+			inner: Arc::new(Mutex::new(Latest {
+				value: None,
+				generation: 0,
+			})),
+		}
+	}
+
+	fn lock(&self) -> MutexGuard<'_, Latest<T>> {
+		// The cache is always in a consistent state, even after a panic.
+		self.inner.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	/// Store a new value.
+	pub fn set(&self, value: T) {
+		let mut latest = self.lock();
+		latest.value = Some(value);
+		latest.generation += 1;
+	}
+
+	/// The number of times a value was stored.
+	///
+	/// This can be used to check whether the value changed since it was last
+	/// read.
+	pub fn generation(&self) -> u64 {
+		self.lock().generation
+	}
+}
+
+impl<T: Clone> LatestValue<T> {
+	/// Get a copy of the latest value, if any value was stored yet.
+	pub fn get(&self) -> Option<T> {
+		self.lock().value.clone()
+	}
+
+	/// Get a copy of the latest value together with its
+	/// [`generation`](struct.LatestValue.html#method.generation).
+	pub fn get_with_generation(&self) -> Option<(T, u64)> {
+		let latest = self.lock();
+		latest.value.clone().map(|v| (v, latest.generation))
+	}
+}
+
+impl<T: Message> LatestValue<T> {
+	/// Copy the latest message from the subscription into the cache, if it
+	/// was updated.
+	///
+	/// Returns whether the value was updated.
+	pub fn update(&self, sub: &Subscription<T>) -> Result<bool, i32> {
+		if sub.check()? {
+			self.set(sub.get()?);
+			Ok(true)
+		} else {
+			Ok(false)
+		}
+	}
+}
+
+impl<T> Clone for LatestValue<T> {
+	fn clone(&self) -> Self {
+		LatestValue {
+			inner: self.inner.clone(),
+		}
+	}
+}
+
+impl<T> Default for LatestValue<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -41,20 +41,60 @@
 //!
 //! publ.publish(&foo { timestamp: 123, a: 4, b: 5 }).unwrap();
 //! ```
+//!
+//! ## Channels
+//!
+//! For threads which are not time critical, a subscription or publisher can
+//! be moved to a background thread which forwards messages from or to a
+//! `std::sync::mpsc` channel. See
+//! [`Subscription::into_receiver`](struct.Subscription.html#method.into_receiver) and
+//! [`Publisher::into_sender`](struct.Publisher.html#method.into_sender).
+//!
+//! ## Timestamps
+//!
+//! Messages with a `uint64 timestamp` field implement
+//! [`Timestamped`](trait.Timestamped.html), which allows
+//! [`publish_now`](struct.Publisher.html#method.publish_now) to fill in the
+//! current time automatically:
+//!
+//! ```ignore
+//! publ.publish_now(&mut foo { timestamp: 0, a: 4, b: 5 }).unwrap();
+//! ```
 
 mod c;
+mod channel;
+mod latest;
 mod publish;
 mod subscribe;
 
-pub use self::c::{priority, Metadata};
-pub use self::publish::{Publish, Publisher};
+pub use self::c::{priority, Metadata, PollFd};
+pub use self::channel::Pump;
+pub use self::latest::LatestValue;
+pub use self::publish::{AdvertiseError, Publish, PublishError, Publisher};
 pub use self::subscribe::{Subscribe, Subscription};
 
 /// A message which can be published and/or subscribed to.
 ///
 /// This trait is automatically implemented for all messages imported using
 /// `#[px4_message]`.
+///
+/// # Safety
+///
+/// The metadata must match the memory layout of the type exactly, as the
+/// uORB functions copy `metadata().size()` bytes in and out of it.
 pub unsafe trait Message {
 	/// Get the metadata of this type of message.
 	fn metadata() -> &'static Metadata;
 }
+
+/// A message with a `uint64 timestamp` field.
+///
+/// This trait is automatically implemented for all messages imported using
+/// `#[px4_message]` which have such a field.
+pub trait Timestamped: Message {
+	/// Get the `timestamp` field, in microseconds.
+	fn timestamp(&self) -> u64;
+
+	/// Set the `timestamp` field, in microseconds.
+	fn set_timestamp(&mut self, timestamp: u64);
+}
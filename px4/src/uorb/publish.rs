@@ -1,4 +1,7 @@
-use super::{c, priority, Message};
+use super::{c, priority, Message, Timestamped};
+use crate::hrt;
+use std::fmt;
+use std::io;
 use std::marker::PhantomData;
 use std::ptr::null_mut;
 
@@ -20,6 +23,13 @@ enum PublisherState {
 ///
 /// [`Drop`](struct.Publisher.html#impl-Drop)ping the publisher will
 /// unadvertise the message.
+///
+/// ## Threads
+///
+/// A publisher is both `Send` and `Sync`: The `orb_advert_t` handle is a
+/// pointer to the topic, which uORB protects with its own lock. Publishing
+/// requires a `&mut Publisher`, so wrap it in a `Mutex` to publish from
+/// multiple threads.
 pub struct Publisher<T> {
 	state: PublisherState,
 	phantom: PhantomData<fn(T)>,
@@ -28,53 +38,87 @@ pub struct Publisher<T> {
 impl<T: Message> Publisher<T> {
 	/// Publish a message.
 	///
-	/// The first time this function is called, it will advertise the message.
-	pub fn publish(&mut self, value: &T) -> Result<(), i32> {
-		assert_eq!(std::mem::size_of::<T>(), T::metadata().size() as usize);
-		let value_ptr = value as *const T as *const u8;
+	/// The first time this function is called, it will advertise the message,
+	/// unless [`advertise_now`](struct.Publisher.html#method.advertise_now)
+	/// was called before.
+	pub fn publish(&mut self, value: &T) -> Result<(), PublishError> {
 		match self.state {
-			PublisherState::Unadvertised {
-				priority,
-				queue_size,
-			} => {
-				let mut instance = 0i32;
-				let handle = unsafe {
-					c::orb_advertise_multi_queue(
-						T::metadata(),
-						value_ptr,
-						if priority.is_some() {
-							&mut instance
-						} else {
-							null_mut()
-						},
-						priority.unwrap_or(priority::DEFAULT),
-						queue_size,
-					)
-				};
-				if handle == 0 {
-					Err(0)
-				} else {
-					self.state = PublisherState::Advertised {
-						handle,
-						instance: if priority.is_some() {
-							instance as u32
-						} else {
-							u32::max_value()
-						},
-					};
-					Ok(())
-				}
-			}
+			PublisherState::Unadvertised { .. } => Ok(self.advertise_now(value)?),
 			PublisherState::Advertised { handle, .. } => {
+				assert_eq!(std::mem::size_of::<T>(), T::metadata().size() as usize);
+				let value_ptr = value as *const T as *const u8;
 				let r = unsafe { c::orb_publish(T::metadata(), handle, value_ptr) };
 				if r == 0 {
 					Ok(())
 				} else {
-					Err(r)
+					Err(PublishError::Publish(r))
 				}
 			}
 		}
 	}
+
+	/// Set the `timestamp` of the message to the current time, and publish it.
+	///
+	/// See [`hrt::absolute_time`](../hrt/fn.absolute_time.html).
+	pub fn publish_now(&mut self, value: &mut T) -> Result<(), PublishError>
+	where
+		T: Timestamped,
+	{
+		value.set_timestamp(hrt::absolute_time());
+		self.publish(value)
+	}
+
+	/// Advertise the message right away, with the given initial value.
+	///
+	/// This makes the topic visible to other modules (e.g. through
+	/// [`exists`](trait.Subscribe.html#tymethod.exists)) before the first
+	/// call to [`publish`](struct.Publisher.html#method.publish), and reports
+	/// any problem with advertising at a predictable point.
+	///
+	/// Does nothing if the message is already advertised.
+	pub fn advertise_now(&mut self, initial: &T) -> Result<(), AdvertiseError> {
+		let (priority, queue_size) = match self.state {
+			PublisherState::Unadvertised {
+				priority,
+				queue_size,
+			} => (priority, queue_size),
+			PublisherState::Advertised { .. } => return Ok(()),
+		};
+		assert_eq!(std::mem::size_of::<T>(), T::metadata().size() as usize);
+		let mut instance = 0i32;
+		// uORB doesn't set errno for every failure, so don't report a stale one.
+		c::set_errno(0);
+		let handle = unsafe {
+			c::orb_advertise_multi_queue(
+				T::metadata(),
+				initial as *const T as *const u8,
+				if priority.is_some() {
+					&mut instance
+				} else {
+					null_mut()
+				},
+				priority.unwrap_or(priority::DEFAULT),
+				queue_size,
+			)
+		};
+		if handle == 0 {
+			return Err(AdvertiseError {
+				topic: T::metadata().name(),
+				priority,
+				queue_size,
+				errno: c::errno(),
+			});
+		}
+		self.state = PublisherState::Advertised {
+			handle,
+			instance: if priority.is_some() {
+				instance as u32
+			} else {
+				u32::MAX
+			},
+		};
+		Ok(())
+	}
 }
 
 impl<T> Publisher<T> {
@@ -88,27 +132,58 @@ impl<T> Publisher<T> {
 		}
 	}
 
+	/// Set the priority of the message, and advertise it as a new instance.
+	///
+	/// Only has an effect before the message is advertised.
+	/// See [`priority`](priority/index.html) for common values.
+	pub fn priority(mut self, priority: i32) -> Self {
+		if let PublisherState::Unadvertised { priority: p, .. } = &mut self.state {
+			*p = Some(priority);
+		}
+		self
+	}
+
+	/// Advertise the message as a new instance, with the default priority.
+	///
+	/// Only has an effect before the message is advertised.
+	pub fn multi_instance(mut self) -> Self {
+		if let PublisherState::Unadvertised { priority, .. } = &mut self.state {
+			priority.get_or_insert(priority::DEFAULT);
+		}
+		self
+	}
+
+	/// Set the length of the queue of the message.
+	///
+	/// Only has an effect before the message is advertised.
+	pub fn queue_size(mut self, queue_size: u32) -> Self {
+		if let PublisherState::Unadvertised { queue_size: q, .. } = &mut self.state {
+			*q = queue_size;
+		}
+		self
+	}
+
 	/// Check whether the message is already advertised.
 	///
 	/// Will be true after the first call to
-	/// [`publish`](struct.Publisher.html#method.publish).
+	/// [`publish`](struct.Publisher.html#method.publish) or
+	/// [`advertise_now`](struct.Publisher.html#method.advertise_now).
 	pub fn is_advertised(&self) -> bool {
-		match self.state {
-			PublisherState::Advertised { .. } => true,
-			_ => false,
-		}
+		matches!(self.state, PublisherState::Advertised { .. })
 	}
 
 	/// Get the instance number of the published message.
 	///
-	/// Only available after the first call to
-	/// [`publish`](struct.Publisher.html#method.publish),
+	/// Only available after the message is advertised,
 	/// for publishers created through
 	/// [`advertise_multi`](trait.Publish.html#tymethod.advertise_multi) or
-	/// [`advertise_multi_queue`](trait.Publish.html#tymethod.advertise_multi_queue).
+	/// [`advertise_multi_queue`](trait.Publish.html#tymethod.advertise_multi_queue),
+	/// or configured with
+	/// [`priority`](struct.Publisher.html#method.priority) or
+	/// [`multi_instance`](struct.Publisher.html#method.multi_instance).
 	pub fn instance(&self) -> Option<u32> {
 		match self.state {
-			PublisherState::Advertised { instance, .. } if instance != u32::max_value() => {
+			PublisherState::Advertised { instance, .. } if instance != u32::MAX => {
 				Some(instance)
 			}
 			_ => None,
@@ -117,8 +192,7 @@ impl<T> Publisher<T> {
 
 	/// Get the raw `orb_advert_t`.
 	///
-	/// Will return 0 before the first call to
-	/// [`publish`](struct.Publisher.html#method.publish).
+	/// Will return 0 before the message is advertised.
 	pub fn raw_handle(&self) -> usize {
 		match self.state {
 			PublisherState::Advertised { handle, .. } => handle,
@@ -142,6 +216,15 @@ impl<T> Drop for Publisher<T> {
 ///
 /// The functions are lazy: The messages aren't advertised directly, but only
 /// on the first call to [`publish`](struct.Publisher.html#method.publish).
+/// Use [`advertise_now`](struct.Publisher.html#method.advertise_now) to
+/// advertise right away.
+///
+/// The returned publisher can be configured further before it is advertised:
+///
+/// ```ignore
+/// let mut publ = foo::advertise().priority(priority::HIGH).queue_size(4);
+/// publ.advertise_now(&initial)?;
+/// ```
 pub trait Publish {
 	fn advertise() -> Publisher<Self>
 	where
@@ -171,3 +254,79 @@ impl<T: Message> Publish for T {
 		Publisher::new(Some(priority), queue_size)
 	}
 }
+
+/// The error returned when uORB refuses to advertise a message.
+#[derive(Debug)]
+pub struct AdvertiseError {
+	topic: &'static str,
+	priority: Option<i32>,
+	queue_size: u32,
+	errno: i32,
+}
+
+impl AdvertiseError {
+	/// The name of the message that could not be advertised.
+	pub fn topic(&self) -> &'static str {
+		self.topic
+	}
+
+	/// The `errno` value set by uORB, if any.
+	pub fn os_error(&self) -> Option<io::Error> {
+		if self.errno == 0 {
+			None
+		} else {
+			Some(io::Error::from_raw_os_error(self.errno))
+		}
+	}
+}
+
+impl fmt::Display for AdvertiseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "unable to advertise `{}`", self.topic)?;
+		if let Some(priority) = self.priority {
+			write!(f, " as new instance with priority {}", priority)?;
+		}
+		if self.queue_size != 1 {
+			write!(f, " with queue size {}", self.queue_size)?;
+		}
+		match self.os_error() {
+			Some(e) => write!(f, ": {}", e),
+			None => write!(f, ": rejected by uORB"),
+		}
+	}
+}
+
+impl std::error::Error for AdvertiseError {}
+
+/// The error returned by [`Publisher::publish`](struct.Publisher.html#method.publish).
+#[derive(Debug)]
+pub enum PublishError {
+	/// The message was not advertised yet, and advertising it failed.
+	Advertise(AdvertiseError),
+	/// `orb_publish` failed with the given return value.
+	Publish(i32),
+}
+
+impl From<AdvertiseError> for PublishError {
+	fn from(e: AdvertiseError) -> Self {
+		PublishError::Advertise(e)
+	}
+}
+
+impl fmt::Display for PublishError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PublishError::Advertise(e) => e.fmt(f),
+			PublishError::Publish(r) => write!(f, "orb_publish failed with {}", r),
+		}
+	}
+}
+
+impl std::error::Error for PublishError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			PublishError::Advertise(e) => Some(e),
+			PublishError::Publish(_) => None,
+		}
+	}
+}
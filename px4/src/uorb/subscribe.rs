@@ -1,6 +1,13 @@
-use super::{c, Message};
+use super::{c, Message, PollFd};
+use crate::hrt::HrtInstant;
+use crate::ShouldExit;
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
+use std::time::Duration;
+
+/// How long [`Subscription::wait_or_exit`](struct.Subscription.html#method.wait_or_exit)
+/// waits for updates before checking whether exiting is requested.
+const EXIT_CHECK_INTERVAL: Duration = Duration::from_millis(50);
 
 /// A subscription to a [`Message`](trait.Message.html) topic.
 ///
@@ -10,11 +17,29 @@ use std::mem::MaybeUninit;
 ///
 /// [`Drop`](struct.Subscription.html#impl-Drop)ping the subscription will
 /// unsubscribe from the topic.
+///
+/// ## Threads
+///
+/// A subscription can not be shared between threads: uORB keeps track of
+/// which updates were seen through the handle without any synchronization.
+/// To share the latest value of a topic between threads, let one thread own
+/// the subscription and update a [`LatestValue`](struct.LatestValue.html).
+///
+/// Except on NuttX, a subscription can be moved to another thread. On NuttX,
+/// every task has its own file descriptors, so a subscription must be used in
+/// the task that made it.
 pub struct Subscription<T> {
 	handle: i32,
-	phantom: PhantomData<Fn() -> T>,
+	phantom: PhantomData<dyn Fn() -> T>,
 }
 
+// SAFETY: The handle is a file descriptor, which is valid in all threads of
+// the PX4 process. On NuttX, it is only valid within the task that created
+// it, and a `Send` closure can be given to `task::spawn`, so it is not `Send`
+// there.
+#[cfg(not(target_os = "nuttx"))]
+unsafe impl<T> Send for Subscription<T> {}
+
 /// Functions related to subscribing to message topics.
 ///
 /// They are automatically implemented on all [`Message`](trait.Message.html)s.
@@ -83,6 +108,9 @@ impl<T: Message> Subscription<T> {
 
 	/// Copy the latest message into the given message object.
 	///
+	/// # Safety
+	///
+	/// `val` must be valid for writes of a `T`.
 	/// It is safe for `*val` to be uninitialized when calling this function.
 	pub unsafe fn get_into_ptr(&self, val: *mut T) -> Result<(), i32> {
 		assert_eq!(std::mem::size_of::<T>(), T::metadata().size() as usize);
@@ -107,6 +135,52 @@ impl<T: Message> Subscription<T> {
 		}
 	}
 
+	/// Wait until there is an update, or until the timeout passes.
+	///
+	/// Returns whether there was an update.
+	pub fn wait(&self, timeout: Duration) -> Result<bool, i32> {
+		self.wait_inner(timeout, None)
+	}
+
+	/// Wait until there is an update, until the timeout passes, or until
+	/// exiting is requested.
+	///
+	/// Returns whether there was an update.
+	pub fn wait_or_exit(&self, timeout: Duration, should_exit: &ShouldExit) -> Result<bool, i32> {
+		self.wait_inner(timeout, Some(should_exit))
+	}
+
+	fn wait_inner(&self, timeout: Duration, should_exit: Option<&ShouldExit>) -> Result<bool, i32> {
+		let end = HrtInstant::now() + timeout;
+		loop {
+			if self.check()? {
+				return Ok(true);
+			}
+			let now = HrtInstant::now();
+			if now >= end || should_exit.is_some_and(ShouldExit::is_set) {
+				return Ok(false);
+			}
+			let mut timeout = end - now;
+			if should_exit.is_some() {
+				timeout = timeout.min(EXIT_CHECK_INTERVAL);
+			}
+			self.poll(timeout)?;
+		}
+	}
+
+	/// Block until there might be an update, or until the timeout passes.
+	fn poll(&self, timeout: Duration) -> Result<(), i32> {
+		// Round up, such that a timeout below 1 ms doesn't return immediately.
+		let ms = (timeout.as_micros() as u64).div_ceil(1000).min(i32::MAX as u64) as i32;
+		let mut fd = PollFd::new(self.handle);
+		let r = unsafe { c::px4_poll(&mut fd, 1, ms) };
+		if r < 0 {
+			Err(r)
+		} else {
+			Ok(())
+		}
+	}
+
 	/// Get the timestamp of the latest message, in microseconds.
 	pub fn stat(&self) -> Result<u64, i32> {
 		unsafe {
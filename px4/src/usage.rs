@@ -0,0 +1,280 @@
+//! Usage information of a module, in the format used by PX4.
+//!
+//! PX4 modules written in C++ describe themselves using the
+//! `PRINT_MODULE_USAGE_*` macros. In Rust, describe your module with a
+//! [`Usage`](struct.Usage.html), and give it to `#[px4_module_main]`:
+//!
+//! ```
+//! use px4::px4_module_main;
+//! use px4::usage::{Arg, Command, Usage};
+//!
+//! static USAGE: Usage = Usage {
+//!   name: "blink",
+//!   description: "Blinks the LED.",
+//!   commands: &[
+//!     Command {
+//!       name: "on",
+//!       description: Some("Turn the LED on"),
+//!       args: &[
+//!         Arg::Int { option: 'r', description: "Blink rate (Hz)", default: Some(2), min: 1, max: 10, optional: true },
+//!         Arg::Flag { option: 'v', description: "Verbose output", optional: true },
+//!       ],
+//!     },
+//!     Command { name: "off", description: None, args: &[] },
+//!   ],
+//!   args: &[],
+//! };
+//!
+//! #[px4_module_main(usage = "USAGE")]
+//! fn blink_main(args: &[&str]) -> i32 {
+//!   match args.get(1).copied() {
+//!     Some("on") => 0,
+//!     Some("off") => 0,
+//!     _ => {
+//!       USAGE.print();
+//!       1
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! With `usage` given, the module prints the usage information and returns 0
+//! when run as `blink help`, `blink -h` or `blink --help`. If it has commands,
+//! it also prints it when run without any command, but returns 1, like
+//! [`module::main`](../module/fn.main.html). It is also printed after the
+//! error message when its arguments can't be [converted](../args/index.html).
+//! The same information can be exported for the documentation using
+//! [`to_markdown`](struct.Usage.html#method.to_markdown).
+//!
+//! Background modules made with the [`Module`](../module/trait.Module.html)
+//! trait can set [`Module::USAGE`](../module/trait.Module.html#associatedconstant.USAGE)
+//! instead.
+
+use crate::info_raw;
+use log::error;
+use std::ffi::OsStr;
+use std::fmt;
+
+/// The description of a module and its commands.
+#[derive(Clone, Copy, Debug)]
+pub struct Usage {
+	/// The name of the module, as it is called from the shell.
+	pub name: &'static str,
+
+	/// A description of the module.
+	///
+	/// Printed above the usage information. May contain Markdown, like the
+	/// descriptions of PX4's modules.
+	pub description: &'static str,
+
+	/// The commands of the module.
+	///
+	/// If empty, the module is shown as taking only `args`, without a command.
+	pub commands: &'static [Command],
+
+	/// The arguments of a module without commands.
+	pub args: &'static [Arg],
+}
+
+/// A command of a module, such as `start`.
+#[derive(Clone, Copy, Debug)]
+pub struct Command {
+	pub name: &'static str,
+	pub description: Option<&'static str>,
+	pub args: &'static [Arg],
+}
+
+impl Command {
+	/// The `stop`, `status` and `log` commands, as provided by [`module::main`](../module/fn.main.html).
+	pub const DEFAULT_COMMANDS: [Command; 3] = [
+		Command { name: "stop", description: None, args: &[] },
+		Command { name: "status", description: Some("print status info"), args: &[] },
+		Command {
+			name: "log",
+			description: Some("show or set the log filter"),
+			args: &[Arg::Positional { values: "<filter>", description: "e.g. warn,my_module=debug", optional: true }],
+		},
+	];
+}
+
+/// An argument of a command.
+///
+/// `option` is the character of an option, such as `'r'` for `-r`.
+#[derive(Clone, Copy, Debug)]
+pub enum Arg {
+	/// An option with an integer value: `-r <val>`.
+	Int {
+		option: char,
+		description: &'static str,
+		default: Option<i32>,
+		min: i32,
+		max: i32,
+		optional: bool,
+	},
+	/// An option with a floating point value: `-r <val>`.
+	Float {
+		option: char,
+		description: &'static str,
+		default: Option<f32>,
+		min: f32,
+		max: f32,
+		optional: bool,
+	},
+	/// An option without value: `-v`.
+	Flag {
+		option: char,
+		description: &'static str,
+		optional: bool,
+	},
+	/// An option with a string value: `-d <val>`.
+	///
+	/// `values` describes the possible values, e.g. `"<file>"` or `"a|b"`.
+	String {
+		option: char,
+		description: &'static str,
+		default: Option<&'static str>,
+		values: Option<&'static str>,
+		optional: bool,
+	},
+	/// A positional argument, such as a file name.
+	///
+	/// `values` is shown as the argument, e.g. `"<file>"`.
+	Positional {
+		values: &'static str,
+		description: &'static str,
+		optional: bool,
+	},
+}
+
+impl Usage {
+	/// Print the usage information, using `info_raw!`.
+	pub fn print(&self) {
+		let text = self.to_string();
+		for line in text.split_inclusive('\n') {
+			info_raw!(line);
+		}
+	}
+
+	/// The usage information as Markdown, in the format of PX4's module
+	/// documentation.
+	pub fn to_markdown(&self) -> String {
+		let mut md = format!("## {}\n\n", self.name);
+		if !self.description.is_empty() {
+			md.push_str(self.description.trim_end());
+			md.push_str("\n\n");
+		}
+		md.push_str(&format!("### Usage {{#{}_usage}}\n\n```\n", self.name));
+		self.fmt_usage(&mut md).unwrap();
+		md.push_str("```\n");
+		md
+	}
+
+	fn fmt_usage(&self, f: &mut impl fmt::Write) -> fmt::Result {
+		if self.commands.is_empty() {
+			writeln!(f, "Usage: {} [arguments...]", self.name)?;
+			for arg in self.args {
+				arg.fmt(f)?;
+			}
+		} else {
+			writeln!(f, "Usage: {} <command> [arguments...]", self.name)?;
+			writeln!(f, " Commands:")?;
+			for command in self.commands {
+				match command.description {
+					Some(description) => writeln!(f, "\n   {:<13} {}", command.name, description)?,
+					None => writeln!(f, "\n   {}", command.name)?,
+				}
+				for arg in command.args {
+					arg.fmt(f)?;
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+/// The text printed by [`print`](#method.print).
+impl fmt::Display for Usage {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if !self.description.is_empty() {
+			write!(f, "{}\n\n", self.description.trim_end())?;
+		}
+		self.fmt_usage(f)
+	}
+}
+
+impl Arg {
+	fn fmt(&self, f: &mut impl fmt::Write) -> fmt::Result {
+		fn option(f: &mut impl fmt::Write, option: char, description: &str, optional: bool) -> fmt::Result {
+			if optional {
+				writeln!(f, "     [-{} <val>]  {}", option, description)
+			} else {
+				writeln!(f, "     -{} <val>    {}", option, description)
+			}
+		}
+		match *self {
+			Arg::Int { option: o, description, default, optional, .. } => {
+				option(f, o, description, optional)?;
+				if let (true, Some(default)) = (optional, default) {
+					writeln!(f, "                 default: {}", default)?;
+				}
+			}
+			Arg::Float { option: o, description, default, optional, .. } => {
+				option(f, o, description, optional)?;
+				if let (true, Some(default)) = (optional, default) {
+					writeln!(f, "                 default: {:.1}", default)?;
+				}
+			}
+			Arg::Flag { option, description, optional } => {
+				if optional {
+					writeln!(f, "     [-{}]        {}", option, description)?;
+				} else {
+					writeln!(f, "     -{}           {}", option, description)?;
+				}
+			}
+			Arg::String { option: o, description, default, values, optional } => {
+				option(f, o, description, optional)?;
+				match (values, default) {
+					(Some(values), Some(default)) => {
+						writeln!(f, "                 values: {}, default: {}", values, default)?
+					}
+					(Some(values), None) => writeln!(f, "                 values: {}", values)?,
+					(None, Some(default)) => writeln!(f, "                 default: {}", default)?,
+					(None, None) => {}
+				}
+			}
+			Arg::Positional { values, description, optional } => {
+				if optional {
+					writeln!(f, "     [{:<9}] {}", values, description)?;
+				} else {
+					writeln!(f, "     {:<11} {}", values, description)?;
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+impl Usage {
+	/// The status code to return after printing the usage information, if
+	/// the arguments ask for help or are missing a command.
+	#[doc(hidden)]
+	pub fn _help_status(&self, args: &[&OsStr]) -> Option<i32> {
+		match args.get(1) {
+			None if !self.commands.is_empty() => Some(1),
+			None => None,
+			Some(a) if ["help", "-h", "--help", "usage"].iter().any(|h| a == h) => Some(0),
+			Some(_) => None,
+		}
+	}
+}
+
+#[doc(hidden)]
+pub fn _dispatch_failed(target: &str, usage: &Usage, args: &[&OsStr]) -> i32 {
+	if let Some(status) = usage._help_status(args) {
+		usage.print();
+		return status;
+	}
+	error!(target: target, "unknown command: {}", args[1].to_string_lossy());
+	usage.print();
+	1
+}
@@ -0,0 +1,73 @@
+use px4::px4_message;
+use px4::uorb::{priority, Publish, Subscribe};
+use std::io;
+
+#[px4_message("../example/msg/debug_value.msg")]
+struct early;
+
+#[px4_message("../example/msg/debug_value.msg")]
+struct configured;
+
+#[px4_message("../example/msg/debug_value.msg")]
+struct crowded;
+
+#[test]
+fn advertise_now() {
+	let mut publ = early::advertise();
+	assert!(!publ.is_advertised());
+	assert!(!early::exists(0));
+
+	publ.advertise_now(&early { timestamp: 1, value: 1.0, ind: 1 }).unwrap();
+	assert!(publ.is_advertised());
+	assert!(early::exists(0));
+	assert_ne!(publ.raw_handle(), 0);
+	assert_eq!(publ.instance(), None);
+	assert_eq!(early::subscribe().unwrap().get().unwrap().ind, 1);
+
+	// Already advertised, so the value is not published.
+	publ.advertise_now(&early { timestamp: 2, value: 2.0, ind: 2 }).unwrap();
+	assert_eq!(early::subscribe().unwrap().get().unwrap().ind, 1);
+}
+
+#[test]
+fn priority_and_queue_size() {
+	let mut publ = configured::advertise().priority(priority::HIGH).queue_size(2);
+	let sub = configured::subscribe_multi(0).unwrap();
+	for ind in 1..=3 {
+		publ.publish(&configured { timestamp: 0, value: 0.0, ind }).unwrap();
+	}
+	assert_eq!(publ.instance(), Some(0));
+	assert_eq!(sub.get_priority().unwrap(), priority::HIGH);
+	// The first message was dropped from the queue.
+	assert_eq!(sub.get().unwrap().ind, 2);
+	assert_eq!(sub.get().unwrap().ind, 3);
+
+	// Configuring an advertised publisher has no effect.
+	let publ = publ.priority(priority::LOW).queue_size(5);
+	assert_eq!(publ.instance(), Some(0));
+	assert_eq!(sub.get_priority().unwrap(), priority::HIGH);
+}
+
+#[test]
+fn error() {
+	let value = crowded { timestamp: 0, value: 0.0, ind: 0 };
+	let mut publishers: Vec<_> = (0..px4::mock::uorb::MAX_INSTANCES)
+		.map(|_| crowded::advertise_multi(priority::DEFAULT))
+		.collect();
+	for publ in &mut publishers {
+		publ.advertise_now(&value).unwrap();
+	}
+
+	let err = crowded::advertise_multi_queue(priority::HIGH, 4)
+		.advertise_now(&value)
+		.unwrap_err();
+	assert_eq!(err.topic(), "crowded");
+	assert_eq!(err.os_error().map(|e| e.kind()), Some(io::ErrorKind::AlreadyExists));
+	assert_eq!(
+		err.to_string(),
+		format!(
+			"unable to advertise `crowded` as new instance with priority 100 with queue size 4: {}",
+			io::Error::from_raw_os_error(17)
+		)
+	);
+}
@@ -0,0 +1,49 @@
+use px4::args::{ArgsError, FromArgs};
+use px4::mock::run_module;
+use px4::{px4_module_main, LogLevel};
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+
+struct Rate(u32);
+
+impl FromArgs for Rate {
+	fn from_args(args: &[&OsStr]) -> Result<Self, ArgsError> {
+		let args = Vec::<String>::from_args(args)?;
+		match &args[1..] {
+			[rate] => rate
+				.parse()
+				.map(Rate)
+				.map_err(|e| ArgsError::new(format!("invalid rate: {}", e))),
+			_ => Err(ArgsError::new("usage: test <rate>").with_status(2)),
+		}
+	}
+}
+
+#[px4_module_main]
+fn main(rate: Rate) -> i32 {
+	rate.0 as i32
+}
+
+#[test]
+fn from_args() {
+	assert_eq!(run_module(px4_module_main, &["test", "12"]).status, 12);
+
+	let output = run_module(px4_module_main, &["test"]);
+	assert_eq!(output.status, 2);
+	assert_eq!(output.records[0].level, LogLevel::Error);
+	assert_eq!(output.records[0].message, "usage: test <rate>");
+
+	let output = run_module(px4_module_main, &["test", "x"]);
+	assert_eq!(output.status, 1);
+	assert_eq!(output.records[0].message, "invalid rate: invalid digit found in string");
+}
+
+#[test]
+fn invalid_utf8() {
+	let args = [OsStr::new("test"), OsStr::from_bytes(b"1\xff")];
+	let output = run_module(px4_module_main, &args);
+	assert_eq!(output.status, 1);
+	assert_eq!(output.records.len(), 1);
+	assert_eq!(output.records[0].level, LogLevel::Error);
+	assert_eq!(output.records[0].message, "invalid UTF-8 in argument 1: \"1\u{fffd}\"");
+}
@@ -0,0 +1,60 @@
+#[macro_use]
+#[path = "../common/mod.rs"]
+mod common;
+
+use px4::uorb::{Publish, Subscribe};
+use std::time::{Duration, Instant};
+
+debug_value_messages!(received, sent);
+
+const LONG: Duration = Duration::from_secs(10);
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// The pumps are tested together, since moving the clock forward affects all
+// of them.
+#[test]
+fn pumps() {
+	// Forwarding from uORB to a channel, at most once per 10 seconds.
+	let mut publisher = received::advertise();
+	let (rx, rx_pump) = received::subscribe().unwrap().into_receiver(LONG);
+	publisher.publish(&received::from(1)).unwrap();
+	assert_eq!(rx.recv_timeout(TIMEOUT).unwrap().ind, 1);
+	publisher.publish(&received::from(2)).unwrap();
+	publisher.publish(&received::from(3)).unwrap();
+	assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+
+	// Forwarding from a channel to uORB, at most once per 10 seconds.
+	let sub = sent::subscribe().unwrap();
+	let (tx, tx_pump) = sent::advertise().into_sender(LONG);
+	tx.send(sent::from(1)).unwrap();
+	assert!(sub.wait(TIMEOUT).unwrap());
+	assert_eq!(sub.get().unwrap().ind, 1);
+	tx.send(sent::from(2)).unwrap();
+	tx.send(sent::from(3)).unwrap();
+	assert!(!sub.wait(Duration::from_millis(200)).unwrap());
+
+	// After the interval, only the latest value is forwarded.
+	px4::mock::hrt::advance(LONG);
+	assert_eq!(rx.recv_timeout(TIMEOUT).unwrap().ind, 3);
+	assert!(sub.wait(TIMEOUT).unwrap());
+	assert_eq!(sub.get().unwrap().ind, 3);
+	assert!(!sub.check().unwrap());
+
+	// Both stop promptly, without waiting for the interval.
+	let start = Instant::now();
+	assert!(rx_pump.is_running());
+	drop(rx_pump);
+	tx.send(sent::from(4)).unwrap();
+	let publisher = tx_pump.stop();
+	assert!(start.elapsed() < Duration::from_secs(1));
+	assert!(publisher.is_advertised());
+
+	// A pump stops by itself when the other end of the channel is dropped.
+	let (tx, tx_pump) = sent::advertise().into_sender(Duration::ZERO);
+	drop(tx);
+	let start = Instant::now();
+	while tx_pump.is_running() {
+		assert!(start.elapsed() < TIMEOUT);
+		std::thread::sleep(Duration::from_millis(1));
+	}
+}
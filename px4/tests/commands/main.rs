@@ -0,0 +1,95 @@
+use px4::mock::run_module;
+use px4::{px4_module_main, LogLevel};
+
+/// Tools for testing.
+#[px4_module_main]
+mod mytool {
+	use std::error::Error;
+	use std::sync::atomic::{AtomicU32, Ordering};
+
+	pub static SETUPS: AtomicU32 = AtomicU32::new(0);
+
+	pub struct Sensor {
+		offset: i32,
+	}
+
+	#[px4_setup]
+	pub fn open() -> Result<Sensor, String> {
+		SETUPS.fetch_add(1, Ordering::Relaxed);
+		match std::env::var("MYTOOL_SENSOR") {
+			Ok(s) if s == "missing" => Err("no sensor".to_string()),
+			_ => Ok(Sensor { offset: 10 }),
+		}
+	}
+
+	/// Calibrate the sensors.
+	///
+	/// Not shown in the usage.
+	#[px4_command]
+	pub fn calibrate(sensor: Sensor, args: &[&str]) -> i32 {
+		sensor.offset + args.len() as i32
+	}
+
+	#[px4_command(name = "mon", description = "Monitor the sensors")]
+	pub fn monitor(_sensor: Sensor, args: Vec<String>) -> Result<(), Box<dyn Error>> {
+		let _: u8 = args.get(1).ok_or("missing rate")?.parse()?;
+		Ok(())
+	}
+}
+
+const USAGE: &str = "\
+Tools for testing.
+
+Usage: mytool <command> [arguments...]
+ Commands:
+
+   calibrate     Calibrate the sensors.
+
+   mon           Monitor the sensors
+";
+
+// Also tests the setup function, which uses an environment variable, so
+// there is only one test calling commands.
+#[test]
+fn dispatch() {
+	use std::sync::atomic::Ordering;
+
+	assert_eq!(run_module(px4_module_main, &["mytool", "calibrate", "x"]).status, 12);
+	assert_eq!(mytool::SETUPS.load(Ordering::Relaxed), 1);
+
+	let output = run_module(px4_module_main, &["mytool", "mon", "5"]);
+	assert_eq!(output.status, 0);
+	assert!(output.records.is_empty());
+
+	let output = run_module(px4_module_main, &["mytool", "mon"]);
+	assert_eq!(output.status, 1);
+	assert_eq!(output.records[0].level, LogLevel::Error);
+	assert_eq!(output.records[0].message, "missing rate");
+	assert_eq!(mytool::SETUPS.load(Ordering::Relaxed), 3);
+
+	std::env::set_var("MYTOOL_SENSOR", "missing");
+	let output = run_module(px4_module_main, &["mytool", "calibrate"]);
+	std::env::remove_var("MYTOOL_SENSOR");
+	assert_eq!(output.status, 1);
+	assert_eq!(output.records[0].level, LogLevel::Error);
+	assert_eq!(output.records[0].message, "no sensor");
+
+	// Not called for unknown commands.
+	run_module(px4_module_main, &["mytool", "frobnicate"]);
+	assert_eq!(mytool::SETUPS.load(Ordering::Relaxed), 4);
+}
+
+#[test]
+fn usage() {
+	assert_eq!(mytool::USAGE.to_string(), USAGE);
+
+	let output = run_module(px4_module_main, &["mytool"]);
+	assert_eq!(output.status, 1);
+	assert_eq!(output.raw_output(), USAGE);
+
+	let output = run_module(px4_module_main, &["mytool", "frobnicate"]);
+	assert_eq!(output.status, 1);
+	assert_eq!(output.records[0].level, LogLevel::Error);
+	assert_eq!(output.records[0].message, "unknown command: frobnicate");
+	assert_eq!(output.raw_output(), USAGE);
+}
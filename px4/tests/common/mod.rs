@@ -0,0 +1,16 @@
+//! Helpers shared by the tests, included with `#[path = "../common/mod.rs"]`.
+
+/// Define messages with the fields of `debug_value`, which can be made from
+/// just the `ind` field with `From<i8>`.
+macro_rules! debug_value_messages {
+	($($name:ident),*) => {$(
+		#[px4::px4_message("../example/msg/debug_value.msg")]
+		struct $name;
+
+		impl From<i8> for $name {
+			fn from(ind: i8) -> Self {
+				$name { timestamp: 0, value: 0.0, ind }
+			}
+		}
+	)*};
+}
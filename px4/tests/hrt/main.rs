@@ -0,0 +1,62 @@
+use px4::hrt::{self, HrtInstant};
+use std::time::Duration;
+
+#[test]
+fn arithmetic() {
+	let t = HrtInstant::from_micros(5_000);
+	assert_eq!((t + Duration::from_millis(2)).as_micros(), 7_000);
+	assert_eq!((t - Duration::from_millis(2)).as_micros(), 3_000);
+	// Sub-microsecond parts are truncated.
+	assert_eq!((t + Duration::from_nanos(1_999)).as_micros(), 5_001);
+
+	let mut u = t;
+	u += Duration::from_micros(10);
+	assert_eq!(u.as_micros(), 5_010);
+	u -= Duration::from_micros(20);
+	assert_eq!(u.as_micros(), 4_990);
+
+	assert_eq!(t - u, Duration::from_micros(10));
+	assert_eq!(u - t, Duration::ZERO);
+	assert_eq!(t.duration_since(u), Duration::from_micros(10));
+	assert_eq!(u.duration_since(t), Duration::ZERO);
+	assert_eq!(t.checked_duration_since(u), Some(Duration::from_micros(10)));
+	assert_eq!(u.checked_duration_since(t), None);
+	assert!(u < t);
+}
+
+#[test]
+fn overflow() {
+	let t = HrtInstant::from_micros(5);
+	assert_eq!(t.checked_sub(Duration::from_micros(5)), Some(HrtInstant::from_micros(0)));
+	assert_eq!(t.checked_sub(Duration::from_micros(6)), None);
+	assert_eq!(HrtInstant::from_micros(u64::MAX).checked_add(Duration::from_micros(1)), None);
+	assert_eq!(t.checked_add(Duration::MAX), None);
+}
+
+#[test]
+#[should_panic(expected = "overflow")]
+fn sub_before_start() {
+	let _ = HrtInstant::from_micros(5) - Duration::from_micros(6);
+}
+
+#[test]
+fn conversions() {
+	let t = HrtInstant::from(1234u64);
+	assert_eq!(t, HrtInstant::from_micros(1234));
+	assert_eq!(u64::from(t), 1234);
+	assert_eq!(HrtInstant::default().as_micros(), 0);
+}
+
+#[test]
+fn elapsed() {
+	let then = hrt::absolute_time();
+	let instant = HrtInstant::now();
+	px4::mock::hrt::advance(Duration::from_secs(10));
+	assert!(hrt::elapsed_time(then) >= 10_000_000);
+	assert!(instant.elapsed() >= Duration::from_secs(10));
+
+	// Timestamps in the future give zero.
+	let future = hrt::absolute_time() + 60_000_000;
+	assert_eq!(hrt::elapsed_time(future), 0);
+	assert_eq!(HrtInstant::from_micros(future).elapsed(), Duration::ZERO);
+}
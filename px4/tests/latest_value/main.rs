@@ -0,0 +1,52 @@
+#[macro_use]
+#[path = "../common/mod.rs"]
+mod common;
+
+use px4::uorb::{LatestValue, Publish, Subscribe};
+use std::thread;
+
+debug_value_messages!(latest_test);
+
+#[test]
+fn update() {
+	let latest = LatestValue::new();
+	let sub = latest_test::subscribe().unwrap();
+	assert!(latest.get().is_none());
+	assert!(latest.get_with_generation().is_none());
+	assert_eq!(latest.update(&sub), Ok(false));
+	assert_eq!(latest.generation(), 0);
+
+	let mut publisher = latest_test::advertise();
+	publisher.publish(&latest_test::from(1)).unwrap();
+	assert_eq!(latest.update(&sub), Ok(true));
+	assert_eq!(latest.get().unwrap().ind, 1);
+	assert_eq!(latest.generation(), 1);
+
+	// Nothing new was published.
+	assert_eq!(latest.update(&sub), Ok(false));
+	assert_eq!(latest.generation(), 1);
+
+	publisher.publish(&latest_test::from(2)).unwrap();
+	assert_eq!(latest.update(&sub), Ok(true));
+	let (value, generation) = latest.get_with_generation().unwrap();
+	assert_eq!(value.ind, 2);
+	assert_eq!(generation, 2);
+}
+
+#[test]
+fn clones() {
+	let latest = LatestValue::new();
+	let reader = latest.clone();
+	latest.set(latest_test::from(5));
+	let value = thread::spawn(move || reader.get_with_generation())
+		.join()
+		.unwrap();
+	assert_eq!(value.map(|(v, g)| (v.ind, g)), Some((5, 1)));
+
+	let writer = latest.clone();
+	thread::spawn(move || writer.set(latest_test::from(6)))
+		.join()
+		.unwrap();
+	assert_eq!(latest.get().unwrap().ind, 6);
+	assert_eq!(latest.generation(), 2);
+}
@@ -0,0 +1,95 @@
+//! Checks that logging doesn't allocate, and compares it to formatting each
+//! message into a `String` first, as the logger used to do.
+//!
+//! Run with `cargo test --release --test log_alloc -- --nocapture` to see
+//! the timings.
+
+use log::info;
+use px4::mock::{self, run_module};
+use px4::{info_raw, px4_module_main, LogLevel, TRUNCATION_MARKER};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+struct Counting;
+
+// Counted per thread, to not count the allocations of other tests.
+thread_local! {
+	static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.with(|a| a.set(a.get() + 1));
+		System.alloc(layout)
+	}
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const N: usize = 10_000;
+
+/// Runs `f` `N` times, and returns the number of allocations and the time.
+fn measure(mut f: impl FnMut(usize)) -> (usize, Duration) {
+	let allocations = ALLOCATIONS.with(Cell::get);
+	let start = Instant::now();
+	for i in 0..N {
+		f(i);
+	}
+	let time = start.elapsed();
+	(ALLOCATIONS.with(Cell::get) - allocations, time)
+}
+
+#[px4_module_main]
+fn main(args: &[&str]) {
+	if args.get(1) == Some(&"long") {
+		info!("{}", "x".repeat(1000));
+		info_raw!("{}", "é".repeat(300));
+		return;
+	}
+
+	// The old implementation: format into a String, and pass that to PX4.
+	let (before, before_time) = measure(|i| {
+		let s = format!("{}\0{}\0", module_path!(), format_args!("loop {} value {:.3}", i, 0.5));
+		let (module, message) = s.as_bytes().split_at(module_path!().len() + 1);
+		unsafe {
+			mock::log::px4_log_modulename(1, module.as_ptr(), "%s\0".as_ptr(), message.as_ptr());
+		}
+	});
+	let (after, after_time) = measure(|i| info!("loop {} value {:.3}", i, 0.5));
+	let (raw, raw_time) = measure(|i| info_raw!("loop {} value {:.3}\n", i, 0.5));
+
+	eprintln!("format! per record: {:?}, {} allocations", before_time / N as u32, before);
+	eprintln!("info!   per record: {:?}, {} allocations", after_time / N as u32, after);
+	eprintln!("info_raw! per call: {:?}, {} allocations", raw_time / N as u32, raw);
+
+	assert!(before >= N);
+	assert_eq!(after, 0);
+	assert_eq!(raw, 0);
+}
+
+// One test, as messages logged while run_module runs are captured, even if
+// they come from another test.
+#[test]
+fn allocations_and_truncation() {
+	mock::log::set_discard(true);
+	// Call the entry point directly, as run_module captures (and so copies)
+	// all messages.
+	let mut argv = [b"test\0".as_ptr() as *mut u8];
+	assert_eq!(px4_module_main(1, argv.as_mut_ptr()), 0);
+	mock::log::set_discard(false);
+
+	let output = run_module(px4_module_main, &["test", "long"]);
+	let message = &output.records[0].message;
+	assert_eq!(message.len(), px4::LOG_BUFFER_SIZE - 1);
+	assert!(message.ends_with(TRUNCATION_MARKER));
+
+	let raw: Vec<_> = output.records[1..].iter().filter(|r| r.level == LogLevel::Info).collect();
+	assert!(raw.len() > 1);
+	assert!(raw.iter().all(|r| r.message.len() <= px4::LOG_BUFFER_SIZE));
+	assert_eq!(output.raw_output(), "é".repeat(300));
+}
@@ -0,0 +1,63 @@
+use log::{debug, info, trace, LevelFilter};
+use px4::mock::run_module;
+use px4::{px4_module_main, LogFilter};
+
+mod inner {
+	pub fn log() {
+		log::debug!("inner debug");
+	}
+}
+
+#[px4_module_main]
+fn main(args: &[&str]) -> i32 {
+	if args.get(1) == Some(&"log") {
+		return px4::log_command(&args[1..]);
+	}
+	trace!("trace");
+	debug!("debug");
+	info!("info");
+	inner::log();
+	0
+}
+
+fn messages(args: &[&str]) -> Vec<String> {
+	let output = run_module(px4_module_main, args);
+	assert_eq!(output.status, 0);
+	output.records.into_iter().map(|r| r.message).collect()
+}
+
+#[test]
+fn parse() {
+	let filter: LogFilter = "warn, a::b=debug,c=off".parse().unwrap();
+	assert_eq!(filter.to_string(), "warn,a::b=debug,c=off");
+	assert_eq!(filter.level_for("a"), LevelFilter::Warn);
+	assert_eq!(filter.level_for("a::b"), LevelFilter::Debug);
+	assert_eq!(filter.level_for("a::b::c"), LevelFilter::Debug);
+	assert_eq!(filter.level_for("a::bc"), LevelFilter::Warn);
+	assert_eq!(filter.level_for("c"), LevelFilter::Off);
+	assert_eq!(filter.max_level(), LevelFilter::Debug);
+	assert_eq!("".parse::<LogFilter>().unwrap(), LogFilter::default());
+	assert_eq!(
+		"x=loud".parse::<LogFilter>().unwrap_err().to_string(),
+		"invalid log filter directive `x=loud`"
+	);
+}
+
+// Everything using the global logger is in one test, to not run in parallel.
+#[test]
+fn filter() {
+	std::env::set_var(px4::LOG_FILTER_ENV, "log_filter::inner=debug");
+	assert_eq!(messages(&["test"]), ["info", "inner debug"]);
+
+	assert_eq!(messages(&["test", "log", "trace,log_filter::inner=off"]), Vec::<String>::new());
+	assert_eq!(messages(&["test"]), ["trace", "debug", "info"]);
+
+	let output = run_module(px4_module_main, &["test", "log"]);
+	assert_eq!(output.raw_output(), "log filter: trace,log_filter::inner=off\n");
+
+	let output = run_module(px4_module_main, &["test", "log", "x=y"]);
+	assert_eq!(output.status, 1);
+
+	px4::set_log_filter(LogFilter::new(LevelFilter::Warn));
+	assert_eq!(messages(&["test"]), Vec::<String>::new());
+}
@@ -0,0 +1,56 @@
+use log::{debug, info, warn};
+use px4::mock::run_module;
+use px4::uorb::Subscribe;
+use px4::{log_message, px4_module_main, set_log_forwarding, LogForwarding, LogLevel};
+use std::time::Duration;
+
+#[px4_module_main]
+fn main(args: &[&str]) {
+	let n: usize = args[1].parse().unwrap();
+	debug!("not forwarded");
+	// Already published by PX4.
+	warn!("not forwarded");
+	for i in 0..n {
+		info!("info {}", i);
+	}
+}
+
+fn messages(sub: &px4::uorb::Subscription<log_message>) -> Vec<(u8, String)> {
+	let mut messages = Vec::new();
+	while sub.check().unwrap() {
+		let m = sub.get().unwrap();
+		messages.push((m.severity(), m.text().to_string()));
+	}
+	messages
+}
+
+#[test]
+fn forwarding() {
+	let sub = log_message::subscribe().unwrap();
+
+	run_module(px4_module_main, &["test", "1"]);
+	assert_eq!(messages(&sub), []);
+
+	set_log_forwarding(Some(LogForwarding {
+		level: LogLevel::Info,
+		burst: 3,
+		interval: Duration::from_secs(1),
+	}));
+	run_module(px4_module_main, &["test", "2"]);
+	assert_eq!(messages(&sub), [(6, "info 0".to_string()), (6, "info 1".to_string())]);
+
+	// One token left; two dropped.
+	run_module(px4_module_main, &["test", "3"]);
+	assert_eq!(messages(&sub), [(6, "info 0".to_string())]);
+
+	px4::mock::hrt::advance(Duration::from_secs(2));
+	run_module(px4_module_main, &["test", "1"]);
+	assert_eq!(
+		messages(&sub),
+		[(4, "2 log messages dropped".to_string()), (6, "info 0".to_string())]
+	);
+
+	set_log_forwarding(None);
+	run_module(px4_module_main, &["test", "1"]);
+	assert_eq!(messages(&sub), []);
+}
@@ -0,0 +1,45 @@
+use px4::mock::run_module;
+use px4::{error_every_n, info_once, px4_module_main, warn_throttled};
+use std::time::Duration;
+
+#[px4_module_main]
+fn main(args: &[&str]) {
+	let n: usize = args[1].parse().unwrap();
+	for i in 0..n {
+		warn_throttled!(Duration::from_secs(1), "throttled {}", i);
+		info_once!("once {}", i);
+		error_every_n!(3, "every third {}", i);
+	}
+}
+
+fn messages(args: &[&str]) -> Vec<String> {
+	run_module(px4_module_main, args)
+		.records
+		.into_iter()
+		.map(|r| r.message)
+		.collect()
+}
+
+#[test]
+fn rate_limited() {
+	assert_eq!(
+		messages(&["test", "5"]),
+		[
+			"throttled 0",
+			"once 0",
+			"every third 0",
+			"every third 3 (2 similar messages suppressed)",
+		]
+	);
+
+	px4::mock::hrt::advance(Duration::from_secs(1));
+	assert_eq!(
+		messages(&["test", "2"]),
+		[
+			"throttled 0 (4 similar messages suppressed)",
+			"every third 1 (2 similar messages suppressed)",
+		]
+	);
+
+	assert_eq!(messages(&["test", "1"]), Vec::<String>::new());
+}
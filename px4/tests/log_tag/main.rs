@@ -0,0 +1,88 @@
+use log::info;
+use px4::mock::run_module;
+use px4::{px4_module_main, set_log_tag, LogTag};
+
+mod control {
+	pub fn run() {
+		log::warn!("from control");
+	}
+}
+
+#[px4_module_main]
+fn main(args: &[&str]) {
+	info!("hello");
+	control::run();
+	if args.len() > 1 {
+		panic!("bye");
+	}
+}
+
+mod firmware {
+	use px4::task::{self, priority};
+	use px4::{px4_module_main, set_log_tag, LogTag};
+
+	#[px4_module_main(builtin = "blink")]
+	fn main(_args: &[&str]) {
+		log::info!("blink");
+	}
+
+	mod beep {
+		use super::*;
+
+		#[px4_module_main(builtin = "beep")]
+		fn main(args: &[&str]) {
+			if args.len() > 1 {
+				set_log_tag(LogTag::new("beeper"));
+			}
+			let task = task::spawn("beep_task", priority::DEFAULT, 8192, || log::info!("task")).unwrap();
+			while !task.is_finished() {
+				std::thread::yield_now();
+			}
+		}
+	}
+
+	// `run_module` takes the signature of a `dyn` module.
+	pub extern "C" fn blink(argc: u32, argv: *mut *mut u8) -> i32 {
+		blink_main(argc as i32, argv as _)
+	}
+
+	pub extern "C" fn beep(argc: u32, argv: *mut *mut u8) -> i32 {
+		beep::beep_main(argc as i32, argv as _)
+	}
+}
+
+fn modules(args: &[&str]) -> Vec<String> {
+	modules_of(px4_module_main, args)
+}
+
+fn modules_of(main: extern "C" fn(u32, *mut *mut u8) -> i32, args: &[&str]) -> Vec<String> {
+	run_module(main, args)
+		.records
+		.into_iter()
+		.filter_map(|r| r.module)
+		.collect()
+}
+
+#[test]
+fn tag() {
+	// Each command logs with its own name, and so do the tasks it spawns.
+	assert_eq!(modules(&["test"]), ["log_tag", "log_tag"]);
+	assert_eq!(modules_of(firmware::blink, &["blink"]), ["blink"]);
+	assert_eq!(modules_of(firmware::beep, &["beep"]), ["beep"]);
+	assert_eq!(modules_of(firmware::beep, &["beep", "tag"]), ["beeper"]);
+	assert_eq!(modules_of(firmware::blink, &["blink"]), ["blink"]);
+	assert_eq!(modules(&["test"]), ["log_tag", "log_tag"]);
+
+	set_log_tag(LogTag {
+		name: "tagger".to_string(),
+		show_target: true,
+	});
+	assert_eq!(
+		modules(&["test", "panic"]),
+		["tagger:log_tag", "tagger:log_tag::control", "tagger"]
+	);
+
+	set_log_tag(LogTag::new("tagger"));
+	assert_eq!(modules(&["test"]), ["tagger", "tagger"]);
+	assert_eq!(px4::log_tag(), LogTag::new("tagger"));
+}
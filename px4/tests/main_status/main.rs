@@ -0,0 +1,73 @@
+use px4::mock::run_module;
+use px4::{px4_module_main, ErrorStatusCode, LogLevel};
+use std::error::Error;
+use std::fmt;
+
+struct Busy;
+
+impl fmt::Display for Busy {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "device busy")
+	}
+}
+
+impl ErrorStatusCode for Busy {
+	fn status_code(&self) -> i32 {
+		16
+	}
+}
+
+#[derive(Debug)]
+struct OnlyDebug;
+
+fn check(args: &[&str]) -> Result<(), Box<dyn Error>> {
+	match args.get(1).copied() {
+		Some("busy") => Err(Busy.to_string().into()),
+		Some(n) => {
+			let _: u8 = n.parse()?;
+			Ok(())
+		}
+		None => Err("missing argument".into()),
+	}
+}
+
+#[px4_module_main]
+fn main(args: &[&str]) -> Result<(), Box<dyn Error>> {
+	check(args)
+}
+
+#[test]
+fn boxed_error() {
+	let output = run_module(px4_module_main, &["test", "12"]);
+	assert_eq!(output.status, 0);
+	assert!(output.records.is_empty());
+
+	let output = run_module(px4_module_main, &["test", "300"]);
+	assert_eq!(output.status, 1);
+	assert_eq!(output.records.len(), 1);
+	assert_eq!(output.records[0].level, LogLevel::Error);
+	assert_eq!(output.records[0].module.as_deref(), Some("main_status"));
+	assert_eq!(output.records[0].message, "number too large to fit in target type");
+}
+
+// The same selection as the one `#[px4_module_main]` makes.
+macro_rules! status {
+	($e:expr) => {{
+		#[allow(unused_imports)]
+		use px4::main_status::{ViaDebug, ViaDisplay, ViaErrorStatusCode, ViaMainStatusCode};
+		px4::MainStatusCode::to_status_code(
+			(&&&&px4::main_status::Wrap::new($e)).__px4_status("main_status"),
+		)
+	}};
+}
+
+#[test]
+fn selection() {
+	assert_eq!(status!(()), 0);
+	assert_eq!(status!(5), 5);
+	assert_eq!(status!(Err::<(), i32>(3)), 3);
+	assert_eq!(status!(Ok::<i32, String>(4)), 4);
+	assert_eq!(status!(Err::<(), _>(Busy)), 16);
+	assert_eq!(status!(Err::<(), _>("oops".to_string())), 1);
+	assert_eq!(status!(Err::<(), _>(OnlyDebug)), 1);
+}
@@ -0,0 +1,105 @@
+#[macro_use]
+#[path = "../common/mod.rs"]
+mod common;
+
+use px4::uorb::{priority, Publish, Subscribe};
+
+debug_value_messages!(single, multi, queued, waited);
+
+#[test]
+fn publish_subscribe() {
+	let sub = single::subscribe().unwrap();
+	assert!(!single::exists(0));
+	assert!(!sub.check().unwrap());
+	assert!(sub.get().is_err());
+
+	let mut publ = single::advertise();
+	publ.publish_now(&mut single::from(1)).unwrap();
+	assert!(single::exists(0));
+	assert_eq!(single::group_count(), 1);
+	assert!(sub.check().unwrap());
+	assert_eq!(sub.get().unwrap().ind, 1);
+	assert!(!sub.check().unwrap());
+	assert!(sub.stat().unwrap() > 0);
+
+	// A new subscription sees the latest value.
+	let sub2 = single::subscribe().unwrap();
+	assert!(sub2.check().unwrap());
+	assert_eq!(sub2.get().unwrap().ind, 1);
+
+	drop(publ);
+	assert!(!single::exists(0));
+}
+
+#[test]
+fn multi_instance() {
+	let mut a = multi::advertise_multi(priority::HIGH);
+	let mut b = multi::advertise().multi_instance();
+	a.publish(&multi { timestamp: 1, value: 1.0, ind: 1 }).unwrap();
+	b.publish(&multi { timestamp: 2, value: 2.0, ind: 2 }).unwrap();
+	assert_eq!(a.instance(), Some(0));
+	assert_eq!(b.instance(), Some(1));
+	assert_eq!(multi::group_count(), 2);
+
+	let sub = multi::subscribe_multi(1).unwrap();
+	assert_eq!(sub.get().unwrap().ind, 2);
+	assert_eq!(sub.get_priority().unwrap(), priority::DEFAULT);
+	assert_eq!(multi::subscribe_multi(0).unwrap().get_priority().unwrap(), priority::HIGH);
+
+	let mut more: Vec<_> = (0..2).map(|_| multi::advertise().multi_instance()).collect();
+	for p in &mut more {
+		p.publish(&multi { timestamp: 3, value: 3.0, ind: 3 }).unwrap();
+	}
+	let err = multi::advertise().multi_instance()
+		.advertise_now(&multi { timestamp: 4, value: 4.0, ind: 4 })
+		.unwrap_err();
+	assert_eq!(err.topic(), "multi");
+}
+
+#[test]
+fn queue() {
+	let mut publ = queued::advertise_queue(3);
+	let sub = queued::subscribe().unwrap();
+	for ind in 0..5 {
+		publ.publish(&queued::from(ind)).unwrap();
+	}
+	let mut received = Vec::new();
+	while sub.check().unwrap() {
+		received.push(sub.get().unwrap().ind);
+	}
+	assert_eq!(received, [2, 3, 4]);
+}
+
+#[test]
+fn wait() {
+	use px4::ShouldExit;
+	use std::time::{Duration, Instant};
+
+	let sub = waited::subscribe().unwrap();
+	let start = Instant::now();
+	assert!(!sub.wait(Duration::from_millis(20)).unwrap());
+	assert!(start.elapsed() >= Duration::from_millis(20));
+
+	// Wakes up as soon as a message is published.
+	let publisher = std::thread::spawn(|| {
+		std::thread::sleep(Duration::from_millis(20));
+		waited::advertise().publish(&waited::from(1)).unwrap();
+	});
+	let start = Instant::now();
+	assert!(sub.wait(Duration::from_secs(10)).unwrap());
+	assert!(start.elapsed() < Duration::from_secs(5));
+	assert_eq!(sub.get().unwrap().ind, 1);
+	publisher.join().unwrap();
+
+	// Stops waiting when exiting is requested.
+	let should_exit = ShouldExit::new();
+	let s = should_exit.clone();
+	let setter = std::thread::spawn(move || {
+		std::thread::sleep(Duration::from_millis(20));
+		s.set();
+	});
+	let start = Instant::now();
+	assert!(!sub.wait_or_exit(Duration::from_secs(10), &should_exit).unwrap());
+	assert!(start.elapsed() < Duration::from_secs(5));
+	setter.join().unwrap();
+}
@@ -0,0 +1,64 @@
+use log::info;
+use px4::mock::run_module;
+use px4::module::Module;
+use px4::{px4_module_main, ShouldExit};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+struct Looper {
+	loops: AtomicU32,
+}
+
+impl Module for Looper {
+	const NAME: &'static str = "looper";
+
+	fn instantiate(args: &[&str]) -> Result<Self, i32> {
+		if args.len() > 1 {
+			return Err(2);
+		}
+		Ok(Looper { loops: AtomicU32::new(0) })
+	}
+
+	fn run(&self, should_exit: &ShouldExit) {
+		while !should_exit.sleep(Duration::from_millis(1)) {
+			self.loops.fetch_add(1, Ordering::Relaxed);
+		}
+	}
+
+	fn print_status(&self) {
+		info!("looping");
+	}
+}
+
+#[px4_module_main]
+fn main(args: &[&str]) -> i32 {
+	px4::module::main::<Looper>(args)
+}
+
+#[test]
+fn lifecycle() {
+	assert_eq!(run_module(px4_module_main, &["looper", "status"]).status, 1);
+	assert_eq!(run_module(px4_module_main, &["looper", "stop"]).status, 1);
+	assert_eq!(run_module(px4_module_main, &["looper", "start", "x"]).status, 2);
+	assert!(!px4::module::is_running::<Looper>());
+
+	assert_eq!(run_module(px4_module_main, &["looper", "start"]).status, 0);
+	assert!(px4::module::is_running::<Looper>());
+	assert_eq!(run_module(px4_module_main, &["looper", "start"]).status, 1);
+
+	let status = run_module(px4_module_main, &["looper", "status"]);
+	assert_eq!(status.status, 0);
+	assert_eq!(status.records[0].message, "looping");
+
+	assert_eq!(run_module(px4_module_main, &["looper", "stop"]).status, 0);
+	assert!(!px4::module::is_running::<Looper>());
+
+	// PX4 shutting down stops the module too.
+	assert_eq!(run_module(px4_module_main, &["looper", "start"]).status, 0);
+	assert!(px4::mock::shutdown::shutdown());
+	assert!(!px4::module::is_running::<Looper>());
+
+	let usage = run_module(px4_module_main, &["looper"]);
+	assert_eq!(usage.status, 1);
+	assert!(usage.raw_output().starts_with("Usage: looper <command>"));
+}
@@ -0,0 +1,55 @@
+use px4::mock::run_module;
+use px4::{px4_module_main, set_panic_report, PanicReport};
+use std::path::PathBuf;
+
+#[px4_module_main]
+fn main(args: &[&str]) {
+	if let Some(dir) = args.get(1) {
+		set_panic_report(PanicReport {
+			backtrace: false,
+			crash_dir: Some(PathBuf::from(dir)),
+		});
+	}
+	panic!("bye");
+}
+
+#[test]
+fn crash_record() {
+	let dir = std::env::temp_dir().join(format!("px4_panic_report_{}", std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	let file = dir.join("rust_panic_panic_report.txt");
+	std::fs::write(&file, "panicked at 'earlier', src/main.rs:1:1\n").unwrap();
+
+	set_panic_report(PanicReport {
+		backtrace: true,
+		crash_dir: Some(dir.clone()),
+	});
+
+	let output = run_module(px4_module_main, &["test"]);
+	assert_eq!(output.status, -1);
+	assert_eq!(output.records[0].message, "previous run panicked: panicked at 'earlier', src/main.rs:1:1");
+	assert!(output.records[1].message.contains("panicked at 'bye', "));
+	assert!(output.raw_output().starts_with("backtrace:\n"));
+	assert_eq!(px4::panic_count(), 1);
+
+	let old: PathBuf = dir.join("rust_panic_panic_report.txt.old");
+	assert!(old.exists());
+	let record = std::fs::read_to_string(&file).unwrap();
+	assert!(record.contains("panicked at 'bye', "));
+	assert!(record.contains("\nbacktrace:\n"));
+
+	// Only reported once.
+	let output = run_module(px4_module_main, &["test"]);
+	assert!(output.records[0].message.contains("panicked at 'bye', "));
+	assert_eq!(px4::panic_count(), 2);
+
+	// Also looked for in a directory set by the module itself.
+	let other = dir.join("other");
+	std::fs::create_dir_all(&other).unwrap();
+	std::fs::write(other.join("rust_panic_panic_report.txt"), "panicked at 'elsewhere'\n").unwrap();
+	let output = run_module(px4_module_main, &["test", other.to_str().unwrap()]);
+	assert_eq!(output.records[0].message, "previous run panicked: panicked at 'elsewhere'");
+	assert!(output.records[1].message.contains("panicked at 'bye', "));
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
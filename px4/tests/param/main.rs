@@ -0,0 +1,41 @@
+use px4::mock;
+use px4::param::{Param, ParamError, ParamType};
+
+#[test]
+fn get_and_set() {
+	mock::param::define("TEST_RATE", 5);
+	mock::param::define("TEST_GAIN", 0.5f32);
+
+	let rate = Param::<i32>::find("TEST_RATE").unwrap();
+	let gain = Param::<f32>::find("TEST_GAIN").unwrap();
+	assert_eq!(rate.name(), "TEST_RATE");
+	assert_eq!(rate.get(), 5);
+	assert_eq!(gain.get(), 0.5);
+
+	rate.set(7).unwrap();
+	assert_eq!(rate.get(), 7);
+	assert_eq!(mock::param::value("TEST_RATE"), Some(mock::param::Value::Int32(7)));
+
+	mock::param::define("TEST_GAIN", 2.5f32);
+	assert_eq!(gain.get(), 2.5);
+}
+
+#[test]
+fn errors() {
+	mock::param::define("TEST_INT", 1);
+
+	let e = Param::<i32>::find("TEST_MISSING").unwrap_err();
+	assert_eq!(e, ParamError::NotFound { name: "TEST_MISSING".to_string() });
+	assert_eq!(e.to_string(), "parameter `TEST_MISSING` not found");
+
+	let e = Param::<f32>::find("TEST_INT").unwrap_err();
+	assert_eq!(
+		e,
+		ParamError::WrongType {
+			name: "TEST_INT".to_string(),
+			expected: ParamType::Float,
+			actual: ParamType::Int32,
+		}
+	);
+	assert_eq!(e.to_string(), "parameter `TEST_INT` is of type int32, not float");
+}
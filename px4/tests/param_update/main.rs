@@ -0,0 +1,57 @@
+use px4::mock;
+use px4::param::ParamSet;
+use px4::px4_parameters;
+use px4::uorb::Publish;
+
+mod firmware {
+	use px4::px4_message;
+
+	#[px4_message("tests/param_update/parameter_update.msg")]
+	pub struct parameter_update;
+
+	pub fn update(instance: u32) -> parameter_update {
+		parameter_update {
+			timestamp: 0,
+			instance,
+			get_count: 0,
+			set_count: 0,
+			find_count: 0,
+			export_count: 0,
+			active: 0,
+			changed: 1,
+			custom_default: 0,
+			future: [u64::MAX; 16],
+		}
+	}
+}
+
+#[px4_parameters(prefix = "UPD_")]
+struct Tuning {
+	/// Gain.
+	#[param(default = 1.0)]
+	gain: f32,
+}
+
+#[test]
+fn larger_message_from_px4() {
+	use px4::uorb::Message;
+	let px4_size = firmware::parameter_update::metadata().size();
+	assert!(px4_size > px4::param::parameter_update::metadata().size());
+
+	// PX4 advertises the topic with its own, larger, definition.
+	let mut publisher = firmware::parameter_update::advertise();
+	publisher.publish_now(&mut firmware::update(1)).unwrap();
+
+	mock::param::define_defaults(Tuning::DEFINITIONS);
+	let mut set = ParamSet::<Tuning>::new().unwrap();
+	assert!(!set.updated());
+
+	assert_eq!(set.gain, 1.0);
+
+	// `orb_copy` copies all of PX4's message, which must not overflow.
+	mock::param::define("UPD_GAIN", 2.0f32);
+	publisher.publish_now(&mut firmware::update(2)).unwrap();
+	assert!(set.updated());
+	assert_eq!(set.gain, 2.0);
+	assert!(!set.updated());
+}
@@ -0,0 +1,96 @@
+use px4::param::{Definition, ParamSet, Value};
+use px4::{mock, px4_parameters};
+
+#[px4_parameters(prefix = "TST_", group = "Test", module = "test")]
+struct Params {
+	/// Blink rate.
+	///
+	/// How often the LED blinks,
+	/// in "blinks" per second.
+	#[param(default = 2, min = 1, max = 10, unit = "Hz")]
+	rate: i32,
+
+	/// Gain.
+	#[param(name = "TST_K", default = 0.5, min = "-1", decimal = 2, reboot_required)]
+	gain: f32,
+}
+
+const YAML: &str = r#"module_name: test
+parameters:
+    - group: "Test"
+      definitions:
+        TST_RATE:
+          description:
+            short: "Blink rate."
+            long: "How often the LED blinks,\nin \"blinks\" per second."
+          type: int32
+          default: 2
+          min: 1
+          max: 10
+          unit: "Hz"
+        TST_K:
+          description:
+            short: "Gain."
+          type: float
+          default: 0.5
+          min: -1.0
+          decimal: 2
+          reboot_required: true
+"#;
+
+#[test]
+fn metadata() {
+	assert_eq!(Params::MODULE_YAML, YAML);
+	assert_eq!(Params::DEFINITIONS[1], Definition {
+		name: "TST_K",
+		short: "Gain.",
+		long: "",
+		default: Value::Float(0.5),
+		min: Some(Value::Float(-1.0)),
+		max: None,
+		unit: None,
+		decimal: Some(2),
+		reboot_required: true,
+	});
+}
+
+#[test]
+fn find() {
+	assert!(Params::find().is_err());
+	mock::param::define_defaults(Params::DEFINITIONS);
+	let params = Params::find().unwrap();
+	assert_eq!(params.rate.get(), 2);
+	assert_eq!(params.gain.get(), 0.5);
+}
+
+#[px4_parameters(prefix = "TSU_")]
+struct Tuning {
+	/// Gain.
+	#[param(default = 1.5)]
+	gain: f32,
+}
+
+#[test]
+fn param_set() {
+	use std::sync::atomic::{AtomicU32, Ordering};
+	use std::sync::Arc;
+
+	mock::param::define_defaults(Tuning::DEFINITIONS);
+	let mut set = ParamSet::<Tuning>::new().unwrap();
+	assert_eq!(set.gain, 1.5);
+	assert!(!set.updated());
+
+	let calls = Arc::new(AtomicU32::new(0));
+	let c = calls.clone();
+	set.on_change(move |values| {
+		assert_eq!(values.gain, 2.0);
+		c.fetch_add(1, Ordering::Relaxed);
+	});
+
+	set.group().gain.set(2.0).unwrap();
+	assert_eq!(set.gain, 1.5);
+	assert!(set.updated());
+	assert_eq!(*set.values(), TuningValues { gain: 2.0 });
+	assert_eq!(calls.load(Ordering::Relaxed), 1);
+	assert!(!set.updated());
+}
@@ -0,0 +1,47 @@
+use log::{info, warn};
+use px4::mock::{run_module, LogRecord};
+use px4::{info_raw, px4_module_main, LogLevel};
+
+#[px4_module_main]
+fn main(args: &[&str]) -> i32 {
+	info!("Hello {}!", args[1]);
+	info_raw!("raw {}\n", args.len());
+	match args.get(2) {
+		Some(&"panic") => panic!("Bye!"),
+		Some(&"warn") => warn!("careful"),
+		_ => {}
+	}
+	args.len() as i32
+}
+
+#[test]
+fn status_and_records() {
+	let output = run_module(px4_module_main, &["test", "World"]);
+	assert_eq!(output.status, 2);
+	assert_eq!(output.records, vec![
+		LogRecord {
+			level: LogLevel::Info,
+			module: Some("run_module".to_string()),
+			message: "Hello World!".to_string(),
+		},
+		LogRecord {
+			level: LogLevel::Info,
+			module: None,
+			message: "raw 2\n".to_string(),
+		},
+	]);
+	assert_eq!(output.raw_output(), "raw 2\n");
+
+	let output = run_module(px4_module_main, &["test", "you", "warn"]);
+	assert_eq!(output.status, 3);
+	assert_eq!(output.records[2].level, LogLevel::Warn);
+}
+
+#[test]
+fn panic() {
+	let output = run_module(px4_module_main, &["test", "World", "panic"]);
+	assert_eq!(output.status, -1);
+	let panic = output.records.last().unwrap();
+	assert_eq!(panic.level, LogLevel::Panic);
+	assert!(panic.message.contains("panicked at 'Bye!'"), "{}", panic.message);
+}
@@ -0,0 +1,28 @@
+use px4::px4_message;
+use px4::uorb::{LatestValue, Publisher, Subscription};
+
+#[px4_message("tests/message_macro/test.msg")]
+struct test_message;
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+// Only compiles if `T` is *not* `Sync`, since otherwise the call to
+// `some_item` below would be ambiguous.
+trait AmbiguousIfSync<A> {
+	fn some_item() {}
+}
+impl<T: ?Sized> AmbiguousIfSync<()> for T {}
+impl<T: ?Sized + Sync> AmbiguousIfSync<u8> for T {}
+
+#[test]
+fn send_sync() {
+	assert_send::<Publisher<test_message>>();
+	assert_sync::<Publisher<test_message>>();
+
+	assert_send::<Subscription<test_message>>();
+	let _ = <Subscription<test_message> as AmbiguousIfSync<_>>::some_item;
+
+	assert_send::<LatestValue<test_message>>();
+	assert_sync::<LatestValue<test_message>>();
+}
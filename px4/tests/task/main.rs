@@ -0,0 +1,32 @@
+use px4::mock::run_module;
+use px4::px4_module_main;
+use px4::task::{self, priority};
+use std::time::Duration;
+
+#[px4_module_main]
+fn main(args: &[&str]) -> i32 {
+	let panic = args[1] == "panic";
+	let task = task::spawn("worker", priority::DEFAULT, 8192, move || {
+		if panic {
+			panic!("worker failed");
+		}
+	})
+	.unwrap();
+	while px4::mock::task::exit_status(task.id()).is_none() {
+		std::thread::sleep(Duration::from_millis(1));
+	}
+	assert!(task.is_finished());
+	px4::mock::task::exit_status(task.id()).unwrap()
+}
+
+#[test]
+fn panic_is_caught() {
+	let output = run_module(px4_module_main, &["test", "ok"]);
+	assert_eq!(output.status, 0);
+	assert_eq!(px4::panic_count(), 0);
+
+	let output = run_module(px4_module_main, &["test", "panic"]);
+	assert_eq!(output.status, -1);
+	assert!(output.records[0].message.contains("panicked at 'worker failed', "));
+	assert_eq!(px4::panic_count(), 1);
+}
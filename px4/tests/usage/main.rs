@@ -0,0 +1,91 @@
+use px4::mock::run_module;
+use px4::px4_module_main;
+use px4::usage::{Arg, Command, Usage};
+use std::ffi::{CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+
+static USAGE: Usage = Usage {
+	name: "blink",
+	description: "Blinks the LED.",
+	commands: &[
+		Command {
+			name: "start",
+			description: None,
+			args: &[
+				Arg::Int { option: 'r', description: "Rate (Hz)", default: Some(2), min: 1, max: 10, optional: true },
+				Arg::Flag { option: 'v', description: "Verbose", optional: true },
+				Arg::String { option: 'd', description: "Device", default: None, values: Some("<file>"), optional: false },
+			],
+		},
+		Command::DEFAULT_COMMANDS[0],
+		Command::DEFAULT_COMMANDS[1],
+	],
+	args: &[],
+};
+
+const TEXT: &str = "\
+Usage: blink <command> [arguments...]
+ Commands:
+
+   start
+     [-r <val>]  Rate (Hz)
+                 default: 2
+     [-v]        Verbose
+     -d <val>    Device
+                 values: <file>
+
+   stop
+
+   status        print status info
+";
+
+#[px4_module_main(usage = "USAGE")]
+fn main(args: &[&str]) -> i32 {
+	args.len() as i32
+}
+
+mod firmware {
+	use px4::px4_module_main;
+
+	#[px4_module_main(usage = "super::USAGE", builtin = "blink")]
+	fn main(args: &[&str]) -> i32 {
+		args.len() as i32
+	}
+}
+
+#[test]
+fn help() {
+	for args in &[&["blink", "help"][..], &["blink", "-h"]] {
+		let output = run_module(px4_module_main, args);
+		assert_eq!(output.status, 0);
+		assert_eq!(output.raw_output(), format!("Blinks the LED.\n\n{}", TEXT));
+	}
+	// Like `module::main`, a missing command is an error.
+	let output = run_module(px4_module_main, &["blink"]);
+	assert_eq!(output.status, 1);
+	assert_eq!(output.raw_output(), format!("Blinks the LED.\n\n{}", TEXT));
+	assert_eq!(run_module(px4_module_main, &["blink", "start"]).status, 2);
+}
+
+#[test]
+fn bad_arguments() {
+	let output = run_module(px4_module_main, &[OsStr::new("blink"), OsStr::from_bytes(b"\xff")]);
+	assert_eq!(output.status, 1);
+	assert_eq!(output.records[0].module.as_deref(), Some("usage"));
+	assert!(output.raw_output().ends_with(TEXT));
+}
+
+#[test]
+fn markdown() {
+	assert_eq!(
+		USAGE.to_markdown(),
+		format!("## blink\n\nBlinks the LED.\n\n### Usage {{#blink_usage}}\n\n```\n{}```\n", TEXT)
+	);
+}
+
+#[test]
+fn builtin() {
+	let args: Vec<CString> = ["blink", "start", "-v"].iter().map(|&a| CString::new(a).unwrap()).collect();
+	let mut argv: Vec<_> = args.iter().map(|a| a.as_ptr() as *mut _).collect();
+	assert_eq!(firmware::blink_main(3, argv.as_mut_ptr()), 3);
+}
@@ -0,0 +1,22 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn main() {
+	// Export the PX4 symbols defined in main.rs and log.c to the modules we load.
+	println!("cargo:rustc-link-arg-bins=-rdynamic");
+
+	// The logging functions are variadic, so they are defined in C.
+	println!("cargo:rerun-if-changed=build.rs");
+	println!("cargo:rerun-if-changed=src/log.c");
+	println!("cargo:rerun-if-env-changed=CC");
+	let object = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("log.o");
+	let cc = env::var_os("CC").unwrap_or_else(|| "cc".into());
+	let status = Command::new(&cc)
+		.args(["-c", "-fPIC", "-O2", "-Wall", "src/log.c", "-o"])
+		.arg(&object)
+		.status()
+		.unwrap_or_else(|e| panic!("unable to run {:?}: {}", cc, e));
+	assert!(status.success(), "unable to compile src/log.c");
+	println!("cargo:rustc-link-arg-bins={}", object.display());
+}
@@ -0,0 +1,55 @@
+// The logging functions of PX4, which are variadic, and therefore can't be
+// defined in stable Rust. They format the message, and pass it on to the
+// functions in main.rs.
+
+#include <stdarg.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+void px4_loader_log(int level, const char *module_name, const char *message);
+void px4_loader_log_raw(const char *message, size_t len);
+
+// Returns a buffer allocated with malloc, or NULL on failure.
+static char *format(size_t *len, const char *fmt, va_list args)
+{
+	va_list copy;
+	va_copy(copy, args);
+	int n = vsnprintf(NULL, 0, fmt, copy);
+	va_end(copy);
+	if (n < 0) {
+		return NULL;
+	}
+	char *buffer = malloc((size_t)n + 1);
+	if (buffer) {
+		vsnprintf(buffer, (size_t)n + 1, fmt, args);
+		*len = (size_t)n;
+	}
+	return buffer;
+}
+
+void px4_log_modulename(int level, const char *module_name, const char *fmt, ...)
+{
+	size_t len;
+	va_list args;
+	va_start(args, fmt);
+	char *message = format(&len, fmt, args);
+	va_end(args);
+	if (message) {
+		px4_loader_log(level, module_name, message);
+		free(message);
+	}
+}
+
+void px4_log_raw(int level, const char *fmt, ...)
+{
+	(void)level;
+	size_t len;
+	va_list args;
+	va_start(args, fmt);
+	char *message = format(&len, fmt, args);
+	va_end(args);
+	if (message) {
+		px4_loader_log_raw(message, len);
+		free(message);
+	}
+}
@@ -0,0 +1,297 @@
+//! # PX4 module loader
+//!
+//! Runs a PX4 module written in Rust on a host without PX4, the same way
+//! PX4's `dyn` command would:
+//!
+//! ```text
+//! px4_loader [--quiet] [--param NAME=VALUE]... path/to/libmodule.so [arguments...]
+//! ```
+//!
+//! This executable provides the C functions of PX4 which the `px4` crate
+//! uses: logging, uORB, parameters, tasks and the high-resolution timer.
+//! Parameters given with `--param` are defined before the module is run,
+//! as `int32` if the value is an integer, or as `float` otherwise. Log messages and raw
+//! output are printed to standard output, and all advertised and published
+//! messages are shown unless `--quiet` is given. uORB is implemented by the
+//! in-process broker in [`px4::mock`](../px4/mock/index.html).
+//!
+//! The exit status of the loader is the status code returned by the module.
+//!
+//! Note that the module must be built without the `mock` feature of the `px4`
+//! crate. The loader itself uses that feature, which is why it is not part of
+//! the workspace of the other crates.
+
+// The exported functions are only called by the loaded module, and have the
+// same safety requirements as their PX4 counterparts.
+#![allow(clippy::missing_safety_doc)]
+
+use px4::mock;
+use px4::uorb::{Metadata, PollFd};
+use std::ffi::{c_void, CStr, CString, OsString};
+use std::io::Write;
+use std::os::raw::c_char;
+use std::os::unix::ffi::OsStrExt;
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SHOW_TRAFFIC: AtomicBool = AtomicBool::new(true);
+
+fn main() {
+	let mut args: Vec<OsString> = std::env::args_os().skip(1).collect();
+	loop {
+		match args.first().and_then(|a| a.to_str()) {
+			Some("--quiet") | Some("-q") => {
+				SHOW_TRAFFIC.store(false, Ordering::Relaxed);
+				args.remove(0);
+			}
+			Some("--param") | Some("-p") if args.len() > 1 => {
+				let param = args.remove(1);
+				args.remove(0);
+				define_param(&param.to_string_lossy());
+			}
+			_ => break,
+		}
+	}
+	if args.is_empty() {
+		eprintln!("Usage: px4_loader [--quiet] [--param NAME=VALUE]... <module> [arguments...]");
+		exit(2);
+	}
+
+	// Like `dyn`, the path of the module is passed as the first argument.
+	let args: Vec<CString> = args
+		.iter()
+		.map(|a| CString::new(a.as_bytes()).expect("nul byte in argument"))
+		.collect();
+	let mut argv: Vec<*mut u8> = args.iter().map(|a| a.as_ptr() as *mut u8).collect();
+	argv.push(std::ptr::null_mut());
+
+	let status = unsafe {
+		let library = libloading::os::unix::Library::open(
+			Some(&args[0].to_str().expect("invalid UTF-8 in path")),
+			libloading::os::unix::RTLD_NOW,
+		)
+		.unwrap_or_else(|e| {
+			eprintln!("px4_loader: {}", e);
+			exit(2);
+		});
+		let main: libloading::os::unix::Symbol<unsafe extern "C" fn(u32, *mut *mut u8) -> i32> =
+			library.get(b"px4_module_main\0").unwrap_or_else(|e| {
+				eprintln!("px4_loader: {}", e);
+				exit(2);
+			});
+		main(args.len() as u32, argv.as_mut_ptr())
+	};
+
+	let _ = std::io::stdout().flush();
+	exit(status);
+}
+
+fn define_param(param: &str) {
+	let (name, value) = param.split_once('=').unwrap_or_else(|| {
+		eprintln!("px4_loader: invalid parameter: {}", param);
+		exit(2);
+	});
+	if let Ok(value) = value.parse::<i32>() {
+		mock::param::define(name, value);
+	} else if let Ok(value) = value.parse::<f32>() {
+		mock::param::define(name, value);
+	} else {
+		eprintln!("px4_loader: invalid parameter value: {}", param);
+		exit(2);
+	}
+}
+
+// Logging.
+//
+// In C, `px4_log_modulename` and `px4_log_raw` are variadic, which can't be
+// defined in stable Rust. They are defined in log.c, which formats the message
+// and passes it on to these functions.
+
+fn level_name(level: i32) -> &'static str {
+	match level {
+		0 => "DEBUG",
+		1 => "INFO",
+		2 => "WARN",
+		3 => "ERROR",
+		_ => "PANIC",
+	}
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn px4_loader_log(level: i32, module: *const c_char, message: *const c_char) {
+	let module = CStr::from_ptr(module).to_string_lossy();
+	let message = CStr::from_ptr(message).to_string_lossy();
+	println!("{:<5} [{}] {}", level_name(level), module, message);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn px4_loader_log_raw(message: *const u8, len: usize) {
+	let mut stdout = std::io::stdout();
+	let _ = stdout.write_all(std::slice::from_raw_parts(message, len));
+	let _ = stdout.flush();
+}
+
+// High-resolution timer.
+
+#[no_mangle]
+pub unsafe extern "C" fn hrt_absolute_time() -> u64 {
+	mock::hrt::hrt_absolute_time()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn px4_usleep(usec: u32) -> i32 {
+	mock::hrt::px4_usleep(usec)
+}
+
+// Tasks and shutdown.
+
+#[no_mangle]
+pub unsafe extern "C" fn px4_task_spawn_cmd(
+	name: *const c_char,
+	scheduler: i32,
+	priority: i32,
+	stack_size: i32,
+	entry: unsafe extern "C" fn(i32, *mut *mut c_char) -> i32,
+	argv: *const *const c_char,
+) -> i32 {
+	mock::task::px4_task_spawn_cmd(name, scheduler, priority, stack_size, entry, argv)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn px4_register_shutdown_hook(hook: extern "C" fn() -> bool) -> i32 {
+	mock::shutdown::px4_register_shutdown_hook(hook)
+}
+
+// Parameters.
+
+#[no_mangle]
+pub unsafe extern "C" fn param_find(name: *const c_char) -> u16 {
+	mock::param::param_find(name)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn param_type(param: u16) -> u8 {
+	mock::param::param_type(param)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn param_get(param: u16, val: *mut c_void) -> i32 {
+	mock::param::param_get(param, val)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn param_set(param: u16, val: *const c_void) -> i32 {
+	mock::param::param_set(param, val)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn param_notify_changes() {
+	mock::param::param_notify_changes()
+}
+
+// uORB.
+
+unsafe fn show_message(action: &str, meta: *const Metadata, data: *const u8) {
+	if !SHOW_TRAFFIC.load(Ordering::Relaxed) {
+		return;
+	}
+	let meta = &*meta;
+	let mut line = format!("uORB  {} {}", action, meta.name());
+	if !data.is_null() {
+		line.push(':');
+		let data = std::slice::from_raw_parts(data, meta.size_no_padding() as usize);
+		for byte in data {
+			line.push_str(&format!(" {:02x}", byte));
+		}
+	}
+	println!("{}", line);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_advertise_multi_queue(
+	meta: *const Metadata,
+	data: *const u8,
+	instance: *mut i32,
+	priority: i32,
+	queue_size: u32,
+) -> usize {
+	let handle = mock::uorb::orb_advertise_multi_queue(meta, data, instance, priority, queue_size);
+	if handle != 0 {
+		show_message("advertise", meta, data);
+	}
+	handle
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_unadvertise(handle: usize) -> i32 {
+	mock::uorb::orb_unadvertise(handle)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_publish(meta: *const Metadata, handle: usize, data: *const u8) -> i32 {
+	let r = mock::uorb::orb_publish(meta, handle, data);
+	if r == 0 {
+		show_message("publish", meta, data);
+	}
+	r
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_subscribe(meta: *const Metadata) -> i32 {
+	mock::uorb::orb_subscribe(meta)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_subscribe_multi(meta: *const Metadata, instance: u32) -> i32 {
+	mock::uorb::orb_subscribe_multi(meta, instance)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_unsubscribe(handle: i32) -> i32 {
+	mock::uorb::orb_unsubscribe(handle)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_copy(meta: *const Metadata, handle: i32, buffer: *mut u8) -> i32 {
+	mock::uorb::orb_copy(meta, handle, buffer)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_check(handle: i32, updated: *mut bool) -> i32 {
+	mock::uorb::orb_check(handle, updated)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_stat(handle: i32, time: *mut u64) -> i32 {
+	mock::uorb::orb_stat(handle, time)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_exists(meta: *const Metadata, instance: i32) -> i32 {
+	mock::uorb::orb_exists(meta, instance)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_group_count(meta: *const Metadata) -> i32 {
+	mock::uorb::orb_group_count(meta)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_priority(handle: i32, priority: *mut i32) -> i32 {
+	mock::uorb::orb_priority(handle, priority)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_set_interval(handle: i32, interval: u32) -> i32 {
+	mock::uorb::orb_set_interval(handle, interval)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn orb_get_interval(handle: i32, interval: *mut u32) -> i32 {
+	mock::uorb::orb_get_interval(handle, interval)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn px4_poll(fds: *mut PollFd, nfds: u32, timeout: i32) -> i32 {
+	mock::uorb::px4_poll(fds, nfds, timeout)
+}
@@ -0,0 +1,72 @@
+//! Runs the example module in the loader, end to end.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::OnceLock;
+
+/// Build the example module in its own workspace, without the `mock` feature
+/// used by the loader, and return the path of the library.
+fn example() -> &'static Path {
+	static LIBRARY: OnceLock<PathBuf> = OnceLock::new();
+	LIBRARY.get_or_init(|| {
+		let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("../example/Cargo.toml");
+		let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("example");
+		let status = Command::new(env!("CARGO"))
+			.args(["build", "--quiet", "--manifest-path"])
+			.arg(&manifest)
+			.arg("--target-dir")
+			.arg(&target_dir)
+			.status()
+			.expect("unable to run cargo");
+		assert!(status.success(), "unable to build px4_example");
+		target_dir.join("debug").join(format!(
+			"{}px4_example{}",
+			std::env::consts::DLL_PREFIX,
+			std::env::consts::DLL_SUFFIX
+		))
+	})
+}
+
+/// Run the loader in a new, empty, directory, since a panic leaves a crash
+/// record in the working directory.
+fn run(name: &str, args: &[&str]) -> (Output, PathBuf) {
+	let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+	let _ = std::fs::remove_dir_all(&dir);
+	std::fs::create_dir_all(&dir).unwrap();
+	let output = Command::new(env!("CARGO_BIN_EXE_px4_loader"))
+		.arg("--quiet")
+		.arg(example())
+		.args(args)
+		.current_dir(&dir)
+		.output()
+		.unwrap();
+	(output, dir)
+}
+
+#[test]
+fn hello() {
+	let (output, _) = run("hello", &["--name", "Test"]);
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	assert_eq!(output.status.code(), Some(0), "{}", stdout);
+	assert!(stdout.contains("Hello Test!"), "{}", stdout);
+	assert!(stdout.contains("Subscribed and read: Ok("), "{}", stdout);
+}
+
+#[test]
+fn bad_arguments() {
+	let (output, _) = run("bad_arguments", &["--frobnicate"]);
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	assert_eq!(output.status.code(), Some(1), "{}", stdout);
+	assert!(stdout.contains("--frobnicate"), "{}", stdout);
+	assert!(!stdout.contains("Hello"), "{}", stdout);
+}
+
+#[test]
+fn panic() {
+	let (output, dir) = run("panic", &["--panic"]);
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	assert_eq!(output.status.code(), Some(255), "{}", stdout);
+	assert!(stdout.contains("panicked at 'Oh no, panic!'"), "{}", stdout);
+	let record = std::fs::read_to_string(dir.join("rust_panic_px4_example.txt")).unwrap();
+	assert!(record.contains("panicked at 'Oh no, panic!'"), "{}", record);
+}
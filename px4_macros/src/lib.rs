@@ -6,6 +6,7 @@ use proc_macro::TokenStream;
 
 mod message;
 mod module_main;
+mod parameters;
 
 #[proc_macro_attribute]
 pub fn px4_message(args: TokenStream, input: TokenStream) -> TokenStream {
@@ -16,3 +17,18 @@ pub fn px4_message(args: TokenStream, input: TokenStream) -> TokenStream {
 pub fn px4_module_main(args: TokenStream, input: TokenStream) -> TokenStream {
 	module_main::px4_module_main(args, input)
 }
+
+#[proc_macro_attribute]
+pub fn px4_parameters(args: TokenStream, input: TokenStream) -> TokenStream {
+	parameters::px4_parameters(args, input)
+}
+
+#[proc_macro_attribute]
+pub fn px4_command(_args: TokenStream, _input: TokenStream) -> TokenStream {
+	panic!("px4_command can only be used inside a mod with #[px4_module_main]");
+}
+
+#[proc_macro_attribute]
+pub fn px4_setup(_args: TokenStream, _input: TokenStream) -> TokenStream {
+	panic!("px4_setup can only be used inside a mod with #[px4_module_main]");
+}
@@ -27,10 +27,7 @@ pub fn px4_message(args: TokenStream, input: TokenStream) -> TokenStream {
 	let input = parse_macro_input!(input as syn::DeriveInput);
 	let name = input.ident;
 	let is_unit_struct = match input.data {
-		syn::Data::Struct(s) => match s.fields {
-			syn::Fields::Unit => true,
-			_ => false,
-		},
+		syn::Data::Struct(s) => matches!(s.fields, syn::Fields::Unit),
 		_ => false,
 	};
 	if !is_unit_struct || input.generics.lt_token.is_some() {
@@ -121,9 +118,15 @@ pub fn px4_message(args: TokenStream, input: TokenStream) -> TokenStream {
 		members.push((name, width, rust, c, size));
 	}
 
+	// Check for a `uint64 timestamp` field.
+
+	let has_timestamp = members
+		.iter()
+		.any(|(name, _, _, c, _)| name == "timestamp" && c == "uint64_t");
+
 	// Sort the members by alignment, biggest first.
 
-	members.sort_by(|a, b| b.1.cmp(&a.1));
+	members.sort_by_key(|m| std::cmp::Reverse(m.1));
 
 	// Compute the total size and generate the message fields description.
 
@@ -159,6 +162,21 @@ pub fn px4_message(args: TokenStream, input: TokenStream) -> TokenStream {
 	let size = size as u16;
 	let size_no_padding = size_no_padding as u16;
 
+	let timestamped = if has_timestamp {
+		quote! {
+			impl px4::uorb::Timestamped for #name {
+				fn timestamp(&self) -> u64 {
+					self.timestamp
+				}
+				fn set_timestamp(&mut self, timestamp: u64) {
+					self.timestamp = timestamp;
+				}
+			}
+		}
+	} else {
+		quote! {}
+	};
+
 	let expanded = quote! {
 		#[repr(C)]
 		#[repr(align(8))]
@@ -179,6 +197,7 @@ pub fn px4_message(args: TokenStream, input: TokenStream) -> TokenStream {
 				&M
 			}
 		}
+		#timestamped
 	};
 
 	expanded.into()
@@ -2,18 +2,376 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::parse_macro_input;
 
-pub fn px4_module_main(args: TokenStream, input: TokenStream) -> TokenStream {
-	if !args.is_empty() {
-		panic!("px4_module_main does not take any arguments");
+/// The form in which the main function takes its arguments.
+enum ArgsKind {
+	/// `&[&str]`
+	Str,
+	/// `&[&OsStr]`
+	OsStr,
+	/// Anything else, through `FromArgs`.
+	FromArgs(syn::Type),
+}
+
+/// Check if `ty` is `&[&X]`, where the last path segment of `X` is `name`.
+fn is_slice_of_refs_to(ty: &syn::Type, name: &str) -> bool {
+	if let syn::Type::Reference(r) = ty {
+		if let syn::Type::Slice(s) = &*r.elem {
+			if let syn::Type::Reference(r) = &*s.elem {
+				if let syn::Type::Path(p) = &*r.elem {
+					return p.qself.is_none()
+						&& p.path.segments.last().is_some_and(|s| s.value().ident == name);
+				}
+			}
+		}
 	}
-	let fndef = parse_macro_input!(input as syn::ItemFn);
-	let name = &fndef.ident;
-	let expanded = quote! {
-		#fndef
-		#[no_mangle]
-		pub extern "C" fn px4_module_main(argc: u32, argv: *mut *mut u8) -> i32 {
-			unsafe { px4::_run(concat!(module_path!(), "\0").as_bytes(), argc, argv, #name) }
+	false
+}
+
+/// The kind of the last argument of `fndef`, which takes `n` arguments.
+fn args_kind(fndef: &syn::ItemFn, n: usize) -> ArgsKind {
+	let inputs = &fndef.decl.inputs;
+	let message = if n == 1 {
+		"px4_module_main function must take exactly one argument"
+	} else {
+		"px4_command function must take the result of the px4_setup function and the arguments"
+	};
+	if inputs.len() != n {
+		panic!("{}", message);
+	}
+	let ty = match inputs.last().unwrap().into_value() {
+		syn::FnArg::Captured(arg) => &arg.ty,
+		_ => panic!("{}", message),
+	};
+	if is_slice_of_refs_to(ty, "str") {
+		ArgsKind::Str
+	} else if is_slice_of_refs_to(ty, "OsStr") {
+		ArgsKind::OsStr
+	} else {
+		ArgsKind::FromArgs(ty.clone())
+	}
+}
+
+/// The arguments of the attribute.
+#[derive(Default)]
+struct Options {
+	/// `usage = "PATH"`: The `px4::usage::Usage` of the module.
+	usage: Option<syn::Path>,
+	/// `builtin = "NAME"`: Export the entry point as `NAME_main`, instead of
+	/// `px4_module_main`.
+	builtin: Option<String>,
+}
+
+fn parse_options(args: syn::AttributeArgs) -> Options {
+	let mut options = Options::default();
+	for arg in args {
+		match arg {
+			syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv)) if nv.ident == "usage" => {
+				let path = match &nv.lit {
+					syn::Lit::Str(s) => s.parse().ok(),
+					_ => None,
+				};
+				options.usage =
+					Some(path.expect("px4_module_main usage must be a path in a string literal"));
+			}
+			syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv)) if nv.ident == "builtin" => {
+				let name = match &nv.lit {
+					syn::Lit::Str(s) => s.value(),
+					_ => panic!("px4_module_main builtin must be a string literal"),
+				};
+				if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+					panic!("px4_module_main builtin must be a valid command name");
+				}
+				options.builtin = Some(name);
+			}
+			_ => panic!("px4_module_main only takes `usage = \"...\"` and `builtin = \"...\"` arguments"),
 		}
+	}
+	options
+}
+
+pub fn px4_module_main(args: TokenStream, input: TokenStream) -> TokenStream {
+	let options = parse_options(parse_macro_input!(args as syn::AttributeArgs));
+	let (items, entry) = match parse_macro_input!(input as syn::Item) {
+		syn::Item::Fn(fndef) => {
+			let name = &fndef.ident;
+			let entry = entry(quote! { #name }, &fndef, options.usage.as_ref(), true, None);
+			(quote! { #fndef }, entry)
+		}
+		syn::Item::Mod(module) => {
+			if options.usage.is_some() {
+				panic!("px4_module_main on a mod generates its own usage information");
+			}
+			commands(module)
+		}
+		_ => panic!("px4_module_main must be used on a function or a mod"),
+	};
+	// A builtin command only exports its own entry point, such that multiple
+	// commands can be linked into the same firmware image.
+	let entry_point = match options.builtin {
+		Some(name) => {
+			let symbol = syn::Ident::new(&format!("{}_main", name), proc_macro2::Span::call_site());
+			quote! {
+				#[no_mangle]
+				#[allow(clippy::not_unsafe_ptr_arg_deref)]
+				pub extern "C" fn #symbol(argc: i32, argv: *mut *mut std::os::raw::c_char) -> i32 {
+					unsafe {
+						px4::_run(concat!(#name, "\0").as_bytes(), argc as u32, argv as *mut *mut u8, #entry)
+					}
+				}
+			}
+		}
+		None => quote! {
+			#[no_mangle]
+			#[allow(clippy::not_unsafe_ptr_arg_deref)]
+			pub extern "C" fn px4_module_main(argc: u32, argv: *mut *mut u8) -> i32 {
+				unsafe {
+					px4::_run(concat!(module_path!(), "\0").as_bytes(), argc, argv, #entry)
+				}
+			}
+		},
+	};
+	let expanded = quote! {
+		#items
+		#entry_point
 	};
 	expanded.into()
 }
+
+/// Generate the closure given to `px4::_run`, which calls the function
+/// `fndef` (found at `path`) with the right type of arguments.
+///
+/// With a `setup` function, it is called first, and its result is given to
+/// `fndef` before the arguments.
+fn entry(
+	path: proc_macro2::TokenStream,
+	fndef: &syn::ItemFn,
+	usage: Option<&syn::Path>,
+	help: bool,
+	setup: Option<&Setup>,
+) -> proc_macro2::TokenStream {
+	let help = match usage {
+		Some(usage) if help => quote! {
+			if let Some(status) = #usage._help_status(args) {
+				#usage.print();
+				return px4::args::Status::Exit(status);
+			}
+		},
+		_ => quote! {},
+	};
+	let usage = match usage {
+		Some(usage) => quote! { Some(&#usage) },
+		None => quote! { None },
+	};
+	let convert = match args_kind(fndef, if setup.is_some() { 2 } else { 1 }) {
+		ArgsKind::Str => quote! {
+			let args = match px4::args::to_str(args) {
+				Ok(args) => args,
+				Err(e) => return px4::args::_invalid(module_path!(), e, #usage),
+			};
+			let args = &args[..];
+		},
+		ArgsKind::OsStr => quote! {},
+		ArgsKind::FromArgs(ty) => quote! {
+			let args = match <#ty as px4::args::FromArgs>::from_args(args) {
+				Ok(args) => args,
+				Err(e) => return px4::args::_invalid(module_path!(), e, #usage),
+			};
+		},
+	};
+	let (setup, call) = match setup {
+		Some(Setup { path: setup, fallible: false }) => (
+			quote! { let setup = #setup(); },
+			quote! { #path(setup, args) },
+		),
+		Some(Setup { path: setup, fallible: true }) => (
+			quote! {
+				let setup = match #setup() {
+					Ok(setup) => setup,
+					Err(e) => return px4::args::Status::Exit(px4::MainStatusCode::to_status_code(
+						(&&&&px4::main_status::Wrap::new(Err::<(), _>(e))).__px4_status(module_path!())
+					)),
+				};
+			},
+			quote! { #path(setup, args) },
+		),
+		None => (quote! {}, quote! { #path(args) }),
+	};
+	quote! {
+		|args: &[&std::ffi::OsStr]| {
+			#[allow(unused_imports)]
+			use px4::main_status::{ViaDebug, ViaDisplay, ViaErrorStatusCode, ViaMainStatusCode};
+			#help
+			#convert
+			#setup
+			px4::args::Status::Done(
+				(&&&&px4::main_status::Wrap::new(#call)).__px4_status(module_path!())
+			)
+		}
+	}
+}
+
+/// The `#[px4_setup]` function of a mod with commands.
+struct Setup {
+	path: proc_macro2::TokenStream,
+	/// Whether it returns a `Result`, of which only the `Ok` value is given
+	/// to the commands.
+	fallible: bool,
+}
+
+fn is_setup_attr(attr: &syn::Attribute) -> bool {
+	attr.path.segments.last().is_some_and(|s| s.value().ident == "px4_setup")
+}
+
+/// Find the `#[px4_setup]` function in `items`, and remove its attribute.
+fn find_setup(mod_name: &syn::Ident, items: &mut [syn::Item]) -> Option<Setup> {
+	let mut setup = None;
+	for item in items {
+		let fndef = match item {
+			syn::Item::Fn(fndef) => fndef,
+			_ => continue,
+		};
+		let i = match fndef.attrs.iter().position(is_setup_attr) {
+			Some(i) => i,
+			None => continue,
+		};
+		fndef.attrs.remove(i);
+		if setup.is_some() {
+			panic!("px4_module_main mod can only contain one #[px4_setup] function");
+		}
+		if !fndef.decl.inputs.is_empty() {
+			panic!("px4_setup function must not take any arguments");
+		}
+		let fallible = match &fndef.decl.output {
+			syn::ReturnType::Type(_, ty) => match &**ty {
+				syn::Type::Path(p) => p.path.segments.last().is_some_and(|s| s.value().ident == "Result"),
+				_ => false,
+			},
+			syn::ReturnType::Default => false,
+		};
+		let fn_name = &fndef.ident;
+		setup = Some(Setup {
+			path: quote! { #mod_name::#fn_name },
+			fallible,
+		});
+	}
+	setup
+}
+
+/// Options of a `#[px4_command]` attribute.
+#[derive(Default)]
+struct CommandOptions {
+	/// `name = "..."`: The name of the command, if not the function name.
+	name: Option<String>,
+	/// `description = "..."`: The description, if not the doc comment.
+	description: Option<String>,
+}
+
+fn is_command_attr(attr: &syn::Attribute) -> bool {
+	attr.path.segments.last().is_some_and(|s| s.value().ident == "px4_command")
+}
+
+fn parse_command_attr(attr: &syn::Attribute) -> CommandOptions {
+	let mut options = CommandOptions::default();
+	let list = match attr.interpret_meta() {
+		Some(syn::Meta::Word(_)) => return options,
+		Some(syn::Meta::List(list)) => list.nested,
+		_ => panic!("invalid px4_command attribute"),
+	};
+	for arg in list {
+		match arg {
+			syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+				ident,
+				lit: syn::Lit::Str(s),
+				..
+			})) => match ident.to_string().as_str() {
+				"name" => options.name = Some(s.value()),
+				"description" => options.description = Some(s.value()),
+				_ => panic!("unknown px4_command argument `{}`", ident),
+			},
+			_ => panic!("px4_command only takes `name = \"...\"` and `description = \"...\"`"),
+		}
+	}
+	options
+}
+
+/// The doc comment of an item, with the leading space of each line removed.
+pub(crate) fn doc_comment(attrs: &[syn::Attribute]) -> String {
+	let mut lines = Vec::new();
+	for attr in attrs {
+		if let Some(syn::Meta::NameValue(syn::MetaNameValue {
+			ident,
+			lit: syn::Lit::Str(s),
+			..
+		})) = attr.interpret_meta()
+		{
+			if ident == "doc" {
+				let line = s.value();
+				lines.push(line.strip_prefix(' ').unwrap_or(&line).to_string());
+			}
+		}
+	}
+	lines.join("\n").trim().to_string()
+}
+
+/// Handle `#[px4_module_main]` on a `mod` containing `#[px4_command]`
+/// functions: Generate a `USAGE` static in the mod, and a dispatcher which
+/// calls the function for the command given as the first argument, after the
+/// `#[px4_setup]` function, if any.
+fn commands(mut module: syn::ItemMod) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+	let mod_name = module.ident.clone();
+	let (_, items) = module
+		.content
+		.as_mut()
+		.expect("px4_module_main must be used on a mod with a body");
+	let setup = find_setup(&mod_name, items);
+	let mut names = Vec::new();
+	let mut descriptions = Vec::new();
+	let mut entries = Vec::new();
+	for item in items.iter_mut() {
+		let fndef = match item {
+			syn::Item::Fn(fndef) => fndef,
+			_ => continue,
+		};
+		let attr = match fndef.attrs.iter().position(is_command_attr) {
+			Some(i) => fndef.attrs.remove(i),
+			None => continue,
+		};
+		let options = parse_command_attr(&attr);
+		let fn_name = &fndef.ident;
+		names.push(options.name.unwrap_or_else(|| fn_name.to_string()));
+		let description = options.description.unwrap_or_else(|| {
+			doc_comment(&fndef.attrs).lines().next().unwrap_or("").to_string()
+		});
+		descriptions.push(if description.is_empty() {
+			quote! { None }
+		} else {
+			quote! { Some(#description) }
+		});
+		let usage: syn::Path = syn::parse_quote! { #mod_name::USAGE };
+		entries.push(entry(quote! { #mod_name::#fn_name }, fndef, Some(&usage), false, setup.as_ref()));
+	}
+	if names.is_empty() {
+		panic!("px4_module_main mod must contain at least one #[px4_command] function");
+	}
+	let name = mod_name.to_string();
+	let description = doc_comment(&module.attrs);
+	let names = &names;
+	items.push(syn::parse_quote! {
+		/// The usage information of this module, generated by `#[px4_module_main]`.
+		pub static USAGE: px4::usage::Usage = px4::usage::Usage {
+			name: #name,
+			description: #description,
+			commands: &[#(px4::usage::Command { name: #names, description: #descriptions, args: &[] }),*],
+			args: &[],
+		};
+	});
+	let entry = quote! {
+		|args: &[&std::ffi::OsStr]| -> i32 {
+			match args.get(1).and_then(|a| a.to_str()) {
+				#(Some(#names) => px4::MainStatusCode::to_status_code((#entries)(&args[1..])),)*
+				_ => px4::usage::_dispatch_failed(module_path!(), &#mod_name::USAGE, args),
+			}
+		}
+	};
+	(quote! { #module }, entry)
+}
@@ -0,0 +1,295 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use std::fmt::Write;
+use syn::parse_macro_input;
+
+use crate::module_main::doc_comment;
+
+/// The arguments of the attribute on the struct.
+#[derive(Default)]
+struct Options {
+	/// `prefix = "..."`: Prefix for parameter names derived from field names.
+	prefix: Option<String>,
+	/// `group = "..."`: The group shown in ground stations.
+	group: Option<String>,
+	/// `module = "..."`: The module name in the YAML file.
+	module: Option<String>,
+}
+
+/// The arguments of a `#[param(...)]` attribute, as strings.
+#[derive(Default)]
+struct ParamOptions {
+	name: Option<String>,
+	default: Option<String>,
+	min: Option<String>,
+	max: Option<String>,
+	unit: Option<String>,
+	decimal: Option<String>,
+	reboot_required: bool,
+}
+
+/// The value of a literal as a string, allowing numbers in strings, such
+/// that negative values can be given as `min = "-1"`.
+fn lit_string(lit: &syn::Lit) -> String {
+	match lit {
+		syn::Lit::Str(s) => s.value(),
+		syn::Lit::Int(i) => i.value().to_string(),
+		syn::Lit::Float(f) => format!("{:?}", f.value()),
+		syn::Lit::Bool(b) => b.value.to_string(),
+		_ => panic!("Unsupported literal in px4_parameters"),
+	}
+}
+
+fn name_values(nested: impl IntoIterator<Item = syn::NestedMeta>, what: &str) -> Vec<(String, syn::Lit)> {
+	nested
+		.into_iter()
+		.map(|arg| match arg {
+			syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) => (nv.ident.to_string(), nv.lit),
+			syn::NestedMeta::Meta(syn::Meta::Word(ident)) => {
+				(ident.to_string(), syn::Lit::Bool(syn::LitBool { value: true, span: ident.span() }))
+			}
+			_ => panic!("Expected `name = value` arguments in {}", what),
+		})
+		.collect()
+}
+
+/// A parsed numeric value of a parameter of type `ty`.
+fn value(ty: &str, text: &str, field: &str) -> (proc_macro2::TokenStream, String) {
+	match ty {
+		"i32" => {
+			let v: i32 = text.parse().unwrap_or_else(|_| {
+				panic!("Invalid i32 value `{}` for parameter {}", text, field);
+			});
+			(quote! { px4::param::Value::Int32(#v) }, v.to_string())
+		}
+		_ => {
+			let v: f32 = text.parse().unwrap_or_else(|_| {
+				panic!("Invalid f32 value `{}` for parameter {}", text, field);
+			});
+			(quote! { px4::param::Value::Float(#v) }, format!("{:?}", v))
+		}
+	}
+}
+
+/// Quote a string for YAML.
+fn yaml_string(s: &str) -> String {
+	let mut quoted = String::from("\"");
+	for c in s.chars() {
+		match c {
+			'"' => quoted.push_str("\\\""),
+			'\\' => quoted.push_str("\\\\"),
+			'\n' => quoted.push_str("\\n"),
+			c => quoted.push(c),
+		}
+	}
+	quoted.push('"');
+	quoted
+}
+
+fn option_tokens<T: quote::ToTokens>(v: Option<T>) -> proc_macro2::TokenStream {
+	match v {
+		Some(v) => quote! { Some(#v) },
+		None => quote! { None },
+	}
+}
+
+pub fn px4_parameters(args: TokenStream, input: TokenStream) -> TokenStream {
+	// Parse the arguments of the attribute.
+
+	let mut options = Options::default();
+	for (key, lit) in name_values(parse_macro_input!(args as syn::AttributeArgs), "px4_parameters") {
+		let value = Some(lit_string(&lit));
+		match key.as_str() {
+			"prefix" => options.prefix = value,
+			"group" => options.group = value,
+			"module" => options.module = value,
+			_ => panic!("Unknown px4_parameters argument `{}`", key),
+		}
+	}
+
+	// Verify that the input is a struct with named fields.
+
+	let input = parse_macro_input!(input as syn::ItemStruct);
+	let name = &input.ident;
+	let fields = match &input.fields {
+		syn::Fields::Named(fields) => &fields.named,
+		_ => panic!("Expected a struct with named fields"),
+	};
+	if input.generics.lt_token.is_some() {
+		panic!("Expected a struct without generic parameters");
+	}
+
+	let group = options.group.clone().unwrap_or_else(|| name.to_string());
+	let module = options
+		.module
+		.clone()
+		.or_else(|| std::env::var("CARGO_PKG_NAME").ok())
+		.unwrap_or_else(|| name.to_string());
+
+	let mut yaml = String::new();
+	writeln!(yaml, "module_name: {}", module).unwrap();
+	writeln!(yaml, "parameters:").unwrap();
+	writeln!(yaml, "    - group: {}", yaml_string(&group)).unwrap();
+	writeln!(yaml, "      definitions:").unwrap();
+
+	let mut new_fields = Vec::new();
+	let mut definitions = Vec::new();
+	let mut finds = Vec::new();
+	let mut value_fields = Vec::new();
+	let mut gets = Vec::new();
+
+	for field in fields {
+		let ident = field.ident.as_ref().unwrap();
+		let ty = match &field.ty {
+			syn::Type::Path(p) if p.path.is_ident("i32") => "i32",
+			syn::Type::Path(p) if p.path.is_ident("f32") => "f32",
+			_ => panic!("Parameter {} must be of type i32 or f32", ident),
+		};
+
+		// Parse the `#[param(...)]` attribute, and the doc comment.
+
+		let mut attrs = field.attrs.clone();
+		let mut param = ParamOptions::default();
+		if let Some(i) = attrs.iter().position(|a| a.path.is_ident("param")) {
+			let nested = match attrs.remove(i).interpret_meta() {
+				Some(syn::Meta::List(list)) => list.nested,
+				_ => panic!("Expected #[param(...)] on {}", ident),
+			};
+			for (key, lit) in name_values(nested, "param") {
+				let value = Some(lit_string(&lit));
+				match key.as_str() {
+					"name" => param.name = value,
+					"default" => param.default = value,
+					"min" => param.min = value,
+					"max" => param.max = value,
+					"unit" => param.unit = value,
+					"decimal" => param.decimal = value,
+					"reboot_required" => param.reboot_required = value.as_deref() == Some("true"),
+					_ => panic!("Unknown param argument `{}` on {}", key, ident),
+				}
+			}
+		}
+		let param_name = param.name.unwrap_or_else(|| match &options.prefix {
+			Some(prefix) => format!("{}{}", prefix, ident.to_string().to_uppercase()),
+			None => panic!("Parameter {} needs a name, or the struct a prefix", ident),
+		});
+		if param_name.len() > 16 {
+			panic!("Parameter name {} is longer than 16 characters", param_name);
+		}
+		let doc = doc_comment(&attrs);
+		let (short, long) = match doc.find("\n\n") {
+			Some(i) => (doc[..i].replace('\n', " "), doc[i + 2..].trim().to_string()),
+			None => (doc.replace('\n', " "), String::new()),
+		};
+		if short.is_empty() {
+			panic!("Parameter {} needs a doc comment as description", ident);
+		}
+		let (default, default_yaml) = value(ty, param.default.as_deref().unwrap_or("0"), &param_name);
+		let min = param.min.as_deref().map(|v| value(ty, v, &param_name));
+		let max = param.max.as_deref().map(|v| value(ty, v, &param_name));
+		let decimal: Option<u32> = param.decimal.as_deref().map(|d| {
+			d.parse().unwrap_or_else(|_| panic!("Invalid decimal for parameter {}", param_name))
+		});
+
+		// Generate the YAML.
+
+		writeln!(yaml, "        {}:", param_name).unwrap();
+		writeln!(yaml, "          description:").unwrap();
+		writeln!(yaml, "            short: {}", yaml_string(&short)).unwrap();
+		if !long.is_empty() {
+			writeln!(yaml, "            long: {}", yaml_string(&long)).unwrap();
+		}
+		writeln!(yaml, "          type: {}", if ty == "i32" { "int32" } else { "float" }).unwrap();
+		writeln!(yaml, "          default: {}", default_yaml).unwrap();
+		if let Some((_, min)) = &min {
+			writeln!(yaml, "          min: {}", min).unwrap();
+		}
+		if let Some((_, max)) = &max {
+			writeln!(yaml, "          max: {}", max).unwrap();
+		}
+		if let Some(unit) = &param.unit {
+			writeln!(yaml, "          unit: {}", yaml_string(unit)).unwrap();
+		}
+		if let Some(decimal) = decimal {
+			writeln!(yaml, "          decimal: {}", decimal).unwrap();
+		}
+		if param.reboot_required {
+			writeln!(yaml, "          reboot_required: true").unwrap();
+		}
+
+		// Generate the Rust code.
+
+		let vis = &field.vis;
+		let ty = syn::Ident::new(ty, Span::call_site());
+		let attrs = &attrs;
+		new_fields.push(quote! { #(#attrs)* #vis #ident: px4::param::Param<#ty> });
+		value_fields.push(quote! { #(#attrs)* #vis #ident: #ty });
+		gets.push(quote! { #ident: self.#ident.get() });
+		let min = option_tokens(min.map(|m| m.0));
+		let max = option_tokens(max.map(|m| m.0));
+		let unit = option_tokens(param.unit);
+		let decimal = option_tokens(decimal);
+		let reboot_required = param.reboot_required;
+		definitions.push(quote! {
+			px4::param::Definition {
+				name: #param_name,
+				short: #short,
+				long: #long,
+				default: #default,
+				min: #min,
+				max: #max,
+				unit: #unit,
+				decimal: #decimal,
+				reboot_required: #reboot_required,
+			}
+		});
+		finds.push(quote! { #ident: px4::param::Param::find(#param_name)? });
+	}
+
+	let attrs = &input.attrs;
+	let vis = &input.vis;
+	let values_name = syn::Ident::new(&format!("{}Values", name), name.span());
+	let values_doc = format!("The values of the parameters in [`{}`](struct.{}.html).", name, name);
+	let expanded = quote! {
+		#(#attrs)*
+		#vis struct #name {
+			#(#new_fields,)*
+		}
+		impl #name {
+			/// The definitions of the parameters.
+			pub const DEFINITIONS: &'static [px4::param::Definition] = &[#(#definitions),*];
+
+			/// The parameter metadata in the format of PX4's `module.yaml`.
+			///
+			/// PX4 only defines the parameters once this is written to the
+			/// `module.yaml` of the firmware build, which is not done
+			/// automatically. See the `px4::param` module.
+			pub const MODULE_YAML: &'static str = #yaml;
+
+			/// Find all parameters.
+			pub fn find() -> Result<Self, px4::param::ParamError> {
+				Ok(#name {
+					#(#finds,)*
+				})
+			}
+		}
+		#[doc = #values_doc]
+		#[derive(Clone, Copy, Debug, PartialEq)]
+		#vis struct #values_name {
+			#(#value_fields,)*
+		}
+		impl px4::param::ParamGroup for #name {
+			type Values = #values_name;
+			fn find() -> Result<Self, px4::param::ParamError> {
+				#name::find()
+			}
+			fn values(&self) -> #values_name {
+				#values_name {
+					#(#gets,)*
+				}
+			}
+		}
+	};
+	expanded.into()
+}